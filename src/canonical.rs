@@ -0,0 +1,119 @@
+use crate::{Expression, OpKind};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+fn collect_operands<'a>(x: &'a Expression, op_kind: OpKind, operands: &mut Vec<&'a Expression>) {
+    match x {
+        Expression::Add(y, z) | Expression::Multiply(y, z) if x.op_kind() == op_kind => {
+            collect_operands(y, op_kind, operands);
+            collect_operands(z, op_kind, operands);
+        }
+        _ => operands.push(x),
+    }
+}
+
+fn operands_eq(x: &[&Expression], y: &[&Expression]) -> bool {
+    if x.len() != y.len() {
+        return false;
+    }
+    let mut matched = vec![false; y.len()];
+    x.iter().all(
+        |x0| match (0..y.len()).find(|j| !matched[*j] && x0.canonically_eq(y[*j])) {
+            Some(j) => {
+                matched[j] = true;
+                true
+            }
+            None => false,
+        },
+    )
+}
+
+impl Expression {
+    pub fn canonical_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.op_kind().hash(&mut hasher);
+        match self {
+            Expression::Number(x) | Expression::Decimal(x) | Expression::RepeatingDecimal(x) => {
+                x.hash(&mut hasher)
+            }
+            Expression::Negate(x)
+            | Expression::Factorial(x)
+            | Expression::Round(x)
+            | Expression::Subfactorial(x)
+            | Expression::NthPrime(x)
+            | Expression::PrimePi(x)
+            | Expression::DoubleFactorial(x)
+            | Expression::Percent(x) => x.canonical_hash().hash(&mut hasher),
+            Expression::Add(_, _) | Expression::Multiply(_, _) => {
+                let mut operands = vec![];
+                collect_operands(self, self.op_kind(), &mut operands);
+                let mut hashes: Vec<u64> = operands.iter().map(|x| x.canonical_hash()).collect();
+                hashes.sort_unstable();
+                hashes.hash(&mut hasher);
+            }
+            Expression::Average(x, y) => {
+                let (x, y) = (x.canonical_hash(), y.canonical_hash());
+                x.min(y).hash(&mut hasher);
+                x.max(y).hash(&mut hasher);
+            }
+            Expression::Subtract(x, y)
+            | Expression::Divide(x, y)
+            | Expression::Power(x, y)
+            | Expression::Tetration(x, y) => {
+                x.canonical_hash().hash(&mut hasher);
+                y.canonical_hash().hash(&mut hasher);
+            }
+            Expression::Sqrt(x, order) => {
+                order.hash(&mut hasher);
+                x.canonical_hash().hash(&mut hasher);
+            }
+            Expression::Root(x, degree) => {
+                degree.hash(&mut hasher);
+                x.canonical_hash().hash(&mut hasher);
+            }
+            Expression::Log(x, base) => {
+                base.hash(&mut hasher);
+                x.canonical_hash().hash(&mut hasher);
+            }
+        }
+        hasher.finish()
+    }
+
+    pub fn canonically_eq(&self, other: &Expression) -> bool {
+        match (self, other) {
+            (Expression::Number(x), Expression::Number(y))
+            | (Expression::Decimal(x), Expression::Decimal(y))
+            | (Expression::RepeatingDecimal(x), Expression::RepeatingDecimal(y)) => x == y,
+            (Expression::Negate(x), Expression::Negate(y))
+            | (Expression::Factorial(x), Expression::Factorial(y))
+            | (Expression::Round(x), Expression::Round(y))
+            | (Expression::Subfactorial(x), Expression::Subfactorial(y))
+            | (Expression::NthPrime(x), Expression::NthPrime(y))
+            | (Expression::PrimePi(x), Expression::PrimePi(y))
+            | (Expression::DoubleFactorial(x), Expression::DoubleFactorial(y))
+            | (Expression::Percent(x), Expression::Percent(y)) => x.canonically_eq(y),
+            (Expression::Add(_, _), Expression::Add(_, _))
+            | (Expression::Multiply(_, _), Expression::Multiply(_, _)) => {
+                let mut x = vec![];
+                let mut y = vec![];
+                collect_operands(self, self.op_kind(), &mut x);
+                collect_operands(other, other.op_kind(), &mut y);
+                operands_eq(&x, &y)
+            }
+            (Expression::Average(x0, x1), Expression::Average(y0, y1)) => {
+                (x0.canonically_eq(y0) && x1.canonically_eq(y1))
+                    || (x0.canonically_eq(y1) && x1.canonically_eq(y0))
+            }
+            (Expression::Subtract(x0, x1), Expression::Subtract(y0, y1))
+            | (Expression::Divide(x0, x1), Expression::Divide(y0, y1))
+            | (Expression::Power(x0, x1), Expression::Power(y0, y1))
+            | (Expression::Tetration(x0, x1), Expression::Tetration(y0, y1)) => {
+                x0.canonically_eq(y0) && x1.canonically_eq(y1)
+            }
+            (Expression::Sqrt(x, m), Expression::Sqrt(y, n)) => m == n && x.canonically_eq(y),
+            (Expression::Root(x, m), Expression::Root(y, n)) => m == n && x.canonically_eq(y),
+            (Expression::Log(x, m), Expression::Log(y, n)) => m == n && x.canonically_eq(y),
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,345 @@
+use crate::number_theory::{
+    double_factorial, exact_log, factorial, factorial_divide, nth_prime, number_of_digits,
+    prime_pi, subfactorial, tetration,
+};
+use crate::{Domain, Expression, Number, RationalQuadratic, Rc, MAX_QUADRATIC_POWER, PRIMES};
+use num::traits::{CheckedAdd, CheckedDiv, CheckedMul, CheckedSub};
+use num::{checked_pow, One, Signed};
+use std::collections::BTreeSet;
+use std::convert::{TryFrom, TryInto};
+
+const MAX_FACTORIAL: i64 = 20;
+const MAX_PRIME: i64 = 1 << 24;
+const F64_OVERFLOW_FACTORIAL: i64 = 400;
+
+fn checked_sqrt(x: RationalQuadratic) -> Option<RationalQuadratic> {
+    if x.quadratic_power() >= MAX_QUADRATIC_POWER {
+        None
+    } else {
+        x.try_sqrt()
+    }
+}
+
+fn checked_power(x: RationalQuadratic, exponent: i64) -> Option<RationalQuadratic> {
+    if exponent < 0 {
+        RationalQuadratic::one().checked_div(&checked_power(x, -exponent)?)
+    } else {
+        checked_pow(x, exponent as usize)
+    }
+}
+
+fn f64_int(x: f64) -> Option<i64> {
+    if x >= 0.0 && x.fract() == 0.0 && x < (1u64 << 53) as f64 {
+        Some(x as i64)
+    } else {
+        None
+    }
+}
+
+fn factorial_operand_f64(x: f64) -> Option<i64> {
+    Some(f64_int(x)?.min(F64_OVERFLOW_FACTORIAL))
+}
+
+fn evaluate_int(x: &Expression) -> Option<i64> {
+    x.evaluate()?.to_int()
+}
+
+fn evaluate_power(x: &Expression, y: &Expression, sqrt_order: usize) -> Option<RationalQuadratic> {
+    let base = x.evaluate()?;
+    let exponent = y.evaluate()?;
+    let exponent = match exponent.to_int() {
+        Some(exponent) => exponent,
+        None if exponent.is_rational() => {
+            let mut result = base.pow_rational(exponent.rational_part())?;
+            for _ in 0..sqrt_order {
+                result = checked_sqrt(result)?;
+            }
+            return Some(result);
+        }
+        None => return None,
+    };
+    if sqrt_order == 0 {
+        checked_power(base, exponent)
+    } else if sqrt_order < 32 && exponent % (1 << sqrt_order) == 0 {
+        checked_power(base.abs(), exponent >> sqrt_order)
+    } else {
+        let mut result = checked_power(base, exponent)?;
+        for _ in 0..sqrt_order {
+            result = checked_sqrt(result)?;
+        }
+        Some(result)
+    }
+}
+
+fn collect_factors<'a>(x: &'a Rc<Expression>, factors: &mut Vec<&'a Rc<Expression>>) {
+    if let Some((y, z)) = x.to_multiply() {
+        collect_factors(y, factors);
+        collect_factors(z, factors);
+    } else {
+        factors.push(x);
+    }
+}
+
+fn evaluate_divide(x: &Rc<Expression>, y: &Rc<Expression>) -> Option<RationalQuadratic> {
+    let mut numerator = vec![];
+    let mut denominator = vec![];
+    collect_factors(x, &mut numerator);
+    collect_factors(y, &mut denominator);
+    let mut result = RationalQuadratic::one();
+    let mut i = 0;
+    while i < denominator.len() {
+        if let Some(y0) = denominator[i].to_factorial() {
+            if let Some(j) = numerator.iter().position(|z| z.is_factorial()) {
+                let x0 = numerator.remove(j).to_factorial().unwrap();
+                let m = evaluate_int(x0)?;
+                let n = evaluate_int(y0)?;
+                if m < 0 || n < 0 {
+                    return None;
+                }
+                result = if m >= n {
+                    result.checked_mul(&factorial_divide(m, n)?.into())?
+                } else {
+                    result.checked_div(&factorial_divide(n, m)?.into())?
+                };
+                denominator.remove(i);
+                continue;
+            }
+        }
+        i += 1;
+    }
+    for z in numerator {
+        result = result.checked_mul(&z.evaluate()?)?;
+    }
+    for z in denominator {
+        result = result.checked_div(&z.evaluate()?)?;
+    }
+    Some(result)
+}
+
+impl Expression {
+    pub fn evaluate(&self) -> Option<RationalQuadratic> {
+        match self {
+            Expression::Number(x) => Some((*x).into()),
+            Expression::Decimal(x) => RationalQuadratic::from(*x)
+                .checked_div(&checked_pow(10i64, number_of_digits(*x))?.into()),
+            Expression::RepeatingDecimal(x) => RationalQuadratic::from(*x)
+                .checked_div(&(checked_pow(10i64, number_of_digits(*x))? - 1).into()),
+            Expression::Negate(x) => Some(-x.evaluate()?),
+            Expression::Add(x, y) => x.evaluate()?.checked_add(&y.evaluate()?),
+            Expression::Subtract(x, y) => x.evaluate()?.checked_sub(&y.evaluate()?),
+            Expression::Multiply(x, y) => x.evaluate()?.checked_mul(&y.evaluate()?),
+            Expression::Divide(x, y) => evaluate_divide(x, y),
+            Expression::Power(x, y) => evaluate_power(x, y, 0),
+            Expression::Sqrt(x, order) => {
+                if let Some((y, z)) = x.to_power() {
+                    return evaluate_power(y, z, *order);
+                }
+                let mut result = x.evaluate()?;
+                for _ in 0..*order {
+                    result = checked_sqrt(result)?;
+                }
+                Some(result)
+            }
+            Expression::Factorial(x) => {
+                let n = evaluate_int(x)?;
+                if !(0..=MAX_FACTORIAL).contains(&n) {
+                    None
+                } else {
+                    Some(factorial(n)?.into())
+                }
+            }
+            Expression::Average(x, y) => x
+                .evaluate()?
+                .checked_add(&y.evaluate()?)?
+                .checked_div(&2.into()),
+            Expression::Round(x) => Some(x.evaluate()?.round()?.into()),
+            Expression::Subfactorial(x) => {
+                let n = evaluate_int(x)?;
+                if !(0..=MAX_FACTORIAL).contains(&n) {
+                    None
+                } else {
+                    Some(subfactorial(n)?.into())
+                }
+            }
+            Expression::NthPrime(x) => {
+                let k = evaluate_int(x)?;
+                if k > MAX_PRIME / 16 {
+                    None
+                } else {
+                    Some(nth_prime(k)?.into())
+                }
+            }
+            Expression::PrimePi(x) => {
+                let k = evaluate_int(x)?;
+                if k > MAX_PRIME {
+                    None
+                } else {
+                    Some(prime_pi(k).into())
+                }
+            }
+            Expression::DoubleFactorial(x) => {
+                let n = evaluate_int(x)?;
+                if n < 0 {
+                    None
+                } else {
+                    Some(double_factorial(n)?.into())
+                }
+            }
+            Expression::Root(x, degree) => x
+                .evaluate()?
+                .try_nth_root(*degree)
+                .filter(|result| result.quadratic_power() <= MAX_QUADRATIC_POWER),
+            Expression::Percent(x) => x.evaluate()?.checked_div(&100.into()),
+            Expression::Log(x, base) => {
+                let x = x.evaluate()?;
+                if x.is_rational() {
+                    Some(exact_log(*base, x.rational_part())?.into())
+                } else {
+                    None
+                }
+            }
+            Expression::Tetration(x, y) => {
+                let result = tetration(evaluate_int(x)?.into(), evaluate_int(y)?)?;
+                Some(i64::try_from(result).ok()?.into())
+            }
+        }
+    }
+
+    pub fn eval_f64(&self) -> f64 {
+        match self {
+            Expression::Number(x) => x.to_f64(),
+            Expression::Decimal(x) => x.to_f64() / 10f64.powi(number_of_digits(*x) as i32),
+            Expression::RepeatingDecimal(x) => {
+                x.to_f64() / (10f64.powi(number_of_digits(*x) as i32) - 1.0)
+            }
+            Expression::Negate(x) => -x.eval_f64(),
+            Expression::Add(x, y) => x.eval_f64() + y.eval_f64(),
+            Expression::Subtract(x, y) => x.eval_f64() - y.eval_f64(),
+            Expression::Multiply(x, y) => x.eval_f64() * y.eval_f64(),
+            Expression::Divide(x, y) => x.eval_f64() / y.eval_f64(),
+            Expression::Power(x, y) => x.eval_f64().powf(y.eval_f64()),
+            Expression::Sqrt(x, order) => {
+                let mut result = x.eval_f64();
+                for _ in 0..*order {
+                    result = result.sqrt();
+                }
+                result
+            }
+            Expression::Root(x, degree) => {
+                let x = x.eval_f64();
+                if x < 0.0 && degree % 2 == 1 {
+                    -(-x).powf(1.0 / *degree as f64)
+                } else {
+                    x.powf(1.0 / *degree as f64)
+                }
+            }
+            Expression::Factorial(x) => factorial_operand_f64(x.eval_f64())
+                .map_or(f64::NAN, |n| (2..=n).map(|k| k as f64).product()),
+            Expression::DoubleFactorial(x) => factorial_operand_f64(x.eval_f64())
+                .map_or(f64::NAN, |n| {
+                    (2..=n).rev().step_by(2).map(|k| k as f64).product()
+                }),
+            Expression::Subfactorial(x) => {
+                factorial_operand_f64(x.eval_f64()).map_or(f64::NAN, |n| {
+                    (1..=n).fold(1.0, |result, k| {
+                        result * k as f64 + if k % 2 == 0 { 1.0 } else { -1.0 }
+                    })
+                })
+            }
+            Expression::Average(x, y) => (x.eval_f64() + y.eval_f64()) / 2.0,
+            Expression::Round(x) => x.eval_f64().round(),
+            Expression::Percent(x) => x.eval_f64() / 100.0,
+            Expression::Log(x, base) => x.eval_f64().ln() / (*base as f64).ln(),
+            Expression::Tetration(x, y) => {
+                let base = x.eval_f64();
+                let height = y.eval_f64();
+                if height < 0.0 || height.fract() != 0.0 {
+                    return f64::NAN;
+                }
+                let mut result = 1.0;
+                for _ in 0..height as u64 {
+                    let next = base.powf(result);
+                    if next == result {
+                        break;
+                    }
+                    result = next;
+                }
+                result
+            }
+            Expression::NthPrime(x) => f64_int(x.eval_f64())
+                .filter(|k| *k <= MAX_PRIME / 16)
+                .and_then(nth_prime)
+                .map_or(f64::NAN, |p| p as f64),
+            Expression::PrimePi(x) => f64_int(x.eval_f64())
+                .filter(|k| *k <= MAX_PRIME)
+                .map_or(f64::NAN, |k| prime_pi(k) as f64),
+        }
+    }
+
+    pub fn verify<T: TryInto<RationalQuadratic>>(&self, n: i64, expected: T) -> bool {
+        let digit = n.to_string();
+        let mut valid_leaves = true;
+        self.for_each_subexpression(&mut |x| {
+            if let Some(x) = x
+                .to_number()
+                .or_else(|| x.to_decimal())
+                .or_else(|| x.to_repeating_decimal())
+            {
+                let x = x.to_string();
+                valid_leaves &=
+                    x.len() % digit.len() == 0 && x == digit.repeat(x.len() / digit.len());
+            }
+        });
+        valid_leaves
+            && match (self.evaluate(), expected.try_into()) {
+                (Some(x), Ok(y)) => x == y,
+                _ => false,
+            }
+    }
+
+    pub fn max_intermediate(&self) -> Option<RationalQuadratic> {
+        let mut result: Option<RationalQuadratic> = None;
+        let mut evaluable = true;
+        self.for_each_subexpression(&mut |x| {
+            if let Some(value) = x.evaluate() {
+                if result.is_none_or(|y| value.to_f64().abs() > y.to_f64().abs()) {
+                    result = Some(value);
+                }
+            } else {
+                evaluable = false;
+            }
+        });
+        if evaluable {
+            result
+        } else {
+            None
+        }
+    }
+    pub fn domain(&self) -> Domain {
+        let mut domain = Domain::Integral;
+        self.for_each_subexpression(&mut |x| {
+            if let Some(value) = x.evaluate() {
+                if !value.is_rational() {
+                    domain = Domain::Quadratic;
+                } else if !value.is_int() {
+                    domain = domain.max(Domain::Rational);
+                }
+            }
+        });
+        domain
+    }
+
+    pub fn quadratic_field_primes(&self) -> BTreeSet<u32> {
+        let mut primes = BTreeSet::new();
+        self.for_each_subexpression(&mut |x| {
+            if let Some(value) = x.evaluate() {
+                for (prime, power) in PRIMES.iter().zip(value.quadratic_part().iter()) {
+                    if *power > 0 {
+                        primes.insert(*prime as u32);
+                    }
+                }
+            }
+        });
+        primes
+    }
+}
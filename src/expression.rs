@@ -1,6 +1,19 @@
+use crate::number_theory::number_of_base_digits;
+#[cfg(feature = "serde")]
+use crate::FlatNode;
+use crate::{Number, Rc};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
 use std::fmt;
-use std::rc::Rc;
 
+#[derive(Clone, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(into = "Vec<FlatNode>", try_from = "Vec<FlatNode>")
+)]
 pub enum Expression {
     Number(i64),
     Negate(Rc<Expression>),
@@ -11,6 +24,65 @@ pub enum Expression {
     Power(Rc<Expression>, Rc<Expression>),
     Sqrt(Rc<Expression>, usize),
     Factorial(Rc<Expression>),
+    Average(Rc<Expression>, Rc<Expression>),
+    Round(Rc<Expression>),
+    Subfactorial(Rc<Expression>),
+    NthPrime(Rc<Expression>),
+    PrimePi(Rc<Expression>),
+    DoubleFactorial(Rc<Expression>),
+    Root(Rc<Expression>, u32),
+    Decimal(i64),
+    RepeatingDecimal(i64),
+    Percent(Rc<Expression>),
+    Log(Rc<Expression>, i64),
+    Tetration(Rc<Expression>, Rc<Expression>),
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Domain {
+    Integral,
+    Rational,
+    Quadratic,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum OpKind {
+    Number,
+    Negate,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Power,
+    Sqrt,
+    Factorial,
+    Average,
+    Round,
+    Subfactorial,
+    NthPrime,
+    PrimePi,
+    DoubleFactorial,
+    Root,
+    Decimal,
+    RepeatingDecimal,
+    Percent,
+    Log,
+    Tetration,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum DisplayStyle {
+    #[default]
+    Standard,
+    CompactUnaryChains,
+    Annotated,
+    FallingFactorial,
+    Unicode,
+}
+
+pub struct StyledExpression<'a> {
+    expression: &'a Expression,
+    style: DisplayStyle,
 }
 
 impl Expression {
@@ -24,12 +96,35 @@ impl Expression {
 
     #[inline]
     pub fn is_number(&self) -> bool {
+        matches!(self, Expression::Number(_))
+    }
+
+    #[inline]
+    pub fn to_decimal(&self) -> Option<i64> {
+        match self {
+            Expression::Decimal(x) => Some(*x),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn is_decimal(&self) -> bool {
+        matches!(self, Expression::Decimal(_))
+    }
+
+    #[inline]
+    pub fn to_repeating_decimal(&self) -> Option<i64> {
         match self {
-            Expression::Number(_) => true,
-            _ => false,
+            Expression::RepeatingDecimal(x) => Some(*x),
+            _ => None,
         }
     }
 
+    #[inline]
+    pub fn is_repeating_decimal(&self) -> bool {
+        matches!(self, Expression::RepeatingDecimal(_))
+    }
+
     #[inline]
     pub fn to_negate(&self) -> Option<&Rc<Expression>> {
         match self {
@@ -40,10 +135,7 @@ impl Expression {
 
     #[inline]
     pub fn is_negate(&self) -> bool {
-        match self {
-            Expression::Negate(_) => true,
-            _ => false,
-        }
+        matches!(self, Expression::Negate(_))
     }
 
     #[inline]
@@ -56,10 +148,7 @@ impl Expression {
 
     #[inline]
     pub fn is_add(&self) -> bool {
-        match self {
-            Expression::Add(_, _) => true,
-            _ => false,
-        }
+        matches!(self, Expression::Add(_, _))
     }
 
     #[inline]
@@ -72,10 +161,7 @@ impl Expression {
 
     #[inline]
     pub fn is_subtract(&self) -> bool {
-        match self {
-            Expression::Subtract(_, _) => true,
-            _ => false,
-        }
+        matches!(self, Expression::Subtract(_, _))
     }
 
     #[inline]
@@ -88,10 +174,7 @@ impl Expression {
 
     #[inline]
     pub fn is_multiply(&self) -> bool {
-        match self {
-            Expression::Multiply(_, _) => true,
-            _ => false,
-        }
+        matches!(self, Expression::Multiply(_, _))
     }
 
     #[inline]
@@ -104,10 +187,7 @@ impl Expression {
 
     #[inline]
     pub fn is_divide(&self) -> bool {
-        match self {
-            Expression::Divide(_, _) => true,
-            _ => false,
-        }
+        matches!(self, Expression::Divide(_, _))
     }
 
     #[inline]
@@ -120,10 +200,7 @@ impl Expression {
 
     #[inline]
     pub fn is_power(&self) -> bool {
-        match self {
-            Expression::Power(_, _) => true,
-            _ => false,
-        }
+        matches!(self, Expression::Power(_, _))
     }
 
     #[inline]
@@ -136,10 +213,7 @@ impl Expression {
 
     #[inline]
     pub fn is_sqrt(&self) -> bool {
-        match self {
-            Expression::Sqrt(_, _) => true,
-            _ => false,
-        }
+        matches!(self, Expression::Sqrt(_, _))
     }
 
     #[inline]
@@ -152,16 +226,145 @@ impl Expression {
 
     #[inline]
     pub fn is_factorial(&self) -> bool {
+        matches!(self, Expression::Factorial(_))
+    }
+
+    #[inline]
+    pub fn to_average(&self) -> Option<(&Rc<Expression>, &Rc<Expression>)> {
+        match self {
+            Expression::Average(x, y) => Some((x, y)),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn is_average(&self) -> bool {
+        matches!(self, Expression::Average(_, _))
+    }
+
+    #[inline]
+    pub fn to_round(&self) -> Option<&Rc<Expression>> {
+        match self {
+            Expression::Round(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn is_round(&self) -> bool {
+        matches!(self, Expression::Round(_))
+    }
+
+    #[inline]
+    pub fn to_subfactorial(&self) -> Option<&Rc<Expression>> {
+        match self {
+            Expression::Subfactorial(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn is_subfactorial(&self) -> bool {
+        matches!(self, Expression::Subfactorial(_))
+    }
+
+    #[inline]
+    pub fn to_nth_prime(&self) -> Option<&Rc<Expression>> {
+        match self {
+            Expression::NthPrime(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn is_nth_prime(&self) -> bool {
+        matches!(self, Expression::NthPrime(_))
+    }
+
+    #[inline]
+    pub fn to_prime_pi(&self) -> Option<&Rc<Expression>> {
+        match self {
+            Expression::PrimePi(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn is_prime_pi(&self) -> bool {
+        matches!(self, Expression::PrimePi(_))
+    }
+
+    #[inline]
+    pub fn to_double_factorial(&self) -> Option<&Rc<Expression>> {
+        match self {
+            Expression::DoubleFactorial(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn is_double_factorial(&self) -> bool {
+        matches!(self, Expression::DoubleFactorial(_))
+    }
+
+    #[inline]
+    pub fn to_root(&self) -> Option<(&Rc<Expression>, &u32)> {
+        match self {
+            Expression::Root(x, degree) => Some((x, degree)),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn is_root(&self) -> bool {
+        matches!(self, Expression::Root(_, _))
+    }
+
+    #[inline]
+    pub fn to_percent(&self) -> Option<&Rc<Expression>> {
+        match self {
+            Expression::Percent(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn is_percent(&self) -> bool {
+        matches!(self, Expression::Percent(_))
+    }
+
+    #[inline]
+    pub fn to_log(&self) -> Option<(&Rc<Expression>, &i64)> {
+        match self {
+            Expression::Log(x, base) => Some((x, base)),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn is_log(&self) -> bool {
+        matches!(self, Expression::Log(_, _))
+    }
+
+    #[inline]
+    pub fn to_tetration(&self) -> Option<(&Rc<Expression>, &Rc<Expression>)> {
         match self {
-            Expression::Factorial(_) => true,
-            _ => false,
+            Expression::Tetration(x, y) => Some((x, y)),
+            _ => None,
         }
     }
 
+    #[inline]
+    pub fn is_tetration(&self) -> bool {
+        matches!(self, Expression::Tetration(_, _))
+    }
+
     #[inline]
     fn precedence(&self) -> i32 {
         match self {
             Expression::Number(_) => 6,
+            Expression::Decimal(_) => 6,
+            Expression::RepeatingDecimal(_) => 6,
             Expression::Negate(_) => 1,
             Expression::Add(_, _) => 2,
             Expression::Subtract(_, _) => 2,
@@ -170,10 +373,248 @@ impl Expression {
             Expression::Power(_, _) => 4,
             Expression::Sqrt(_, _) => 5,
             Expression::Factorial(_) => 6,
+            Expression::Average(_, _) => 6,
+            Expression::Round(_) => 6,
+            Expression::Subfactorial(_) => 6,
+            Expression::NthPrime(_) => 6,
+            Expression::PrimePi(_) => 6,
+            Expression::DoubleFactorial(_) => 6,
+            Expression::Root(_, _) => 6,
+            Expression::Percent(_) => 6,
+            Expression::Log(_, _) => 6,
+            Expression::Tetration(_, _) => 4,
+        }
+    }
+
+    #[inline]
+    fn is_atomic(&self) -> bool {
+        matches!(
+            self,
+            Expression::Number(_)
+                | Expression::Decimal(_)
+                | Expression::Sqrt(_, _)
+                | Expression::Average(_, _)
+                | Expression::Round(_)
+                | Expression::NthPrime(_)
+                | Expression::PrimePi(_)
+                | Expression::Root(_, _)
+                | Expression::Log(_, _)
+        )
+    }
+
+    // Postfix operators only drop parentheses around literals, so `(sqrt(2))!` stays explicit.
+    #[inline]
+    fn is_literal(&self) -> bool {
+        matches!(
+            self,
+            Expression::Number(_) | Expression::Decimal(_) | Expression::RepeatingDecimal(_)
+        )
+    }
+
+    pub fn op_kind(&self) -> OpKind {
+        match self {
+            Expression::Number(_) => OpKind::Number,
+            Expression::Decimal(_) => OpKind::Decimal,
+            Expression::RepeatingDecimal(_) => OpKind::RepeatingDecimal,
+            Expression::Negate(_) => OpKind::Negate,
+            Expression::Add(_, _) => OpKind::Add,
+            Expression::Subtract(_, _) => OpKind::Subtract,
+            Expression::Multiply(_, _) => OpKind::Multiply,
+            Expression::Divide(_, _) => OpKind::Divide,
+            Expression::Power(_, _) => OpKind::Power,
+            Expression::Sqrt(_, _) => OpKind::Sqrt,
+            Expression::Factorial(_) => OpKind::Factorial,
+            Expression::Average(_, _) => OpKind::Average,
+            Expression::Round(_) => OpKind::Round,
+            Expression::Subfactorial(_) => OpKind::Subfactorial,
+            Expression::NthPrime(_) => OpKind::NthPrime,
+            Expression::PrimePi(_) => OpKind::PrimePi,
+            Expression::DoubleFactorial(_) => OpKind::DoubleFactorial,
+            Expression::Root(_, _) => OpKind::Root,
+            Expression::Percent(_) => OpKind::Percent,
+            Expression::Log(_, _) => OpKind::Log,
+            Expression::Tetration(_, _) => OpKind::Tetration,
         }
     }
+
+    pub fn operator_counts(&self) -> BTreeMap<OpKind, usize> {
+        let mut counts = BTreeMap::new();
+        self.count_operators(&mut counts, false);
+        counts
+    }
+
+    pub fn operator_histogram(&self) -> BTreeMap<OpKind, usize> {
+        let mut histogram = BTreeMap::new();
+        self.count_operators(&mut histogram, true);
+        histogram
+    }
+
+    pub fn operator_count(&self) -> usize {
+        self.operator_counts().values().sum()
+    }
+
+    fn count_operators(&self, counts: &mut BTreeMap<OpKind, usize>, leaves: bool) {
+        match self {
+            Expression::Number(_) | Expression::Decimal(_) | Expression::RepeatingDecimal(_) => {
+                if !leaves {
+                    return;
+                }
+            }
+            Expression::Negate(x)
+            | Expression::Factorial(x)
+            | Expression::Round(x)
+            | Expression::Subfactorial(x)
+            | Expression::NthPrime(x)
+            | Expression::PrimePi(x)
+            | Expression::DoubleFactorial(x)
+            | Expression::Percent(x)
+            | Expression::Log(x, _)
+            | Expression::Root(x, _) => x.count_operators(counts, leaves),
+            Expression::Add(x, y)
+            | Expression::Subtract(x, y)
+            | Expression::Multiply(x, y)
+            | Expression::Divide(x, y)
+            | Expression::Power(x, y)
+            | Expression::Tetration(x, y)
+            | Expression::Average(x, y) => {
+                x.count_operators(counts, leaves);
+                y.count_operators(counts, leaves);
+            }
+            Expression::Sqrt(x, order) => {
+                x.count_operators(counts, leaves);
+                *counts.entry(OpKind::Sqrt).or_insert(0) += order - 1;
+            }
+        }
+        *counts.entry(self.op_kind()).or_insert(0) += 1;
+    }
+
+    #[inline]
+    pub fn depth(&self) -> usize {
+        self.height()
+    }
+
+    pub fn height(&self) -> usize {
+        match self {
+            Expression::Number(_) | Expression::Decimal(_) | Expression::RepeatingDecimal(_) => 1,
+            Expression::Negate(x)
+            | Expression::Factorial(x)
+            | Expression::Round(x)
+            | Expression::Subfactorial(x)
+            | Expression::NthPrime(x)
+            | Expression::PrimePi(x)
+            | Expression::DoubleFactorial(x)
+            | Expression::Percent(x)
+            | Expression::Log(x, _)
+            | Expression::Root(x, _) => x.height() + 1,
+            Expression::Sqrt(x, order) => x.height() + order,
+            Expression::Add(x, y)
+            | Expression::Subtract(x, y)
+            | Expression::Multiply(x, y)
+            | Expression::Divide(x, y)
+            | Expression::Power(x, y)
+            | Expression::Tetration(x, y)
+            | Expression::Average(x, y) => x.height().max(y.height()) + 1,
+        }
+    }
+
+    pub fn sqrt_nesting(&self) -> usize {
+        match self {
+            Expression::Number(_) | Expression::Decimal(_) | Expression::RepeatingDecimal(_) => 0,
+            Expression::Negate(x)
+            | Expression::Factorial(x)
+            | Expression::Round(x)
+            | Expression::Subfactorial(x)
+            | Expression::NthPrime(x)
+            | Expression::PrimePi(x)
+            | Expression::DoubleFactorial(x)
+            | Expression::Percent(x)
+            | Expression::Log(x, _)
+            | Expression::Root(x, _) => x.sqrt_nesting(),
+            Expression::Sqrt(x, order) => x.sqrt_nesting() + order,
+            Expression::Add(x, y)
+            | Expression::Subtract(x, y)
+            | Expression::Multiply(x, y)
+            | Expression::Divide(x, y)
+            | Expression::Power(x, y)
+            | Expression::Tetration(x, y)
+            | Expression::Average(x, y) => x.sqrt_nesting().max(y.sqrt_nesting()),
+        }
+    }
+
+    pub(crate) fn for_each_subexpression(&self, f: &mut impl FnMut(&Expression)) {
+        f(self);
+        match self {
+            Expression::Number(_) | Expression::Decimal(_) | Expression::RepeatingDecimal(_) => {}
+            Expression::Negate(x)
+            | Expression::Sqrt(x, _)
+            | Expression::Factorial(x)
+            | Expression::Round(x)
+            | Expression::Subfactorial(x)
+            | Expression::NthPrime(x)
+            | Expression::PrimePi(x)
+            | Expression::DoubleFactorial(x)
+            | Expression::Percent(x)
+            | Expression::Log(x, _)
+            | Expression::Root(x, _) => x.for_each_subexpression(f),
+            Expression::Add(x, y)
+            | Expression::Subtract(x, y)
+            | Expression::Multiply(x, y)
+            | Expression::Divide(x, y)
+            | Expression::Power(x, y)
+            | Expression::Tetration(x, y)
+            | Expression::Average(x, y) => {
+                x.for_each_subexpression(f);
+                y.for_each_subexpression(f);
+            }
+        }
+    }
+
+    pub fn cost_in_base(&self, base: u32) -> usize {
+        match self {
+            Expression::Number(x) | Expression::Decimal(x) | Expression::RepeatingDecimal(x) => {
+                number_of_base_digits(*x, base).expect("base must be at least 2")
+            }
+            Expression::Negate(x) => x.cost_in_base(base),
+            Expression::Add(x, y)
+            | Expression::Subtract(x, y)
+            | Expression::Multiply(x, y)
+            | Expression::Divide(x, y)
+            | Expression::Power(x, y)
+            | Expression::Tetration(x, y)
+            | Expression::Average(x, y) => x.cost_in_base(base) + y.cost_in_base(base),
+            Expression::Sqrt(x, _) => x.cost_in_base(base),
+            Expression::Factorial(x) => x.cost_in_base(base),
+            Expression::Round(x) => x.cost_in_base(base),
+            Expression::Subfactorial(x) => x.cost_in_base(base),
+            Expression::NthPrime(x) => x.cost_in_base(base),
+            Expression::PrimePi(x) => x.cost_in_base(base),
+            Expression::DoubleFactorial(x) => x.cost_in_base(base),
+            Expression::Root(x, _) => x.cost_in_base(base),
+            Expression::Percent(x) => x.cost_in_base(base),
+            Expression::Log(x, _) => x.cost_in_base(base),
+        }
+    }
+}
+
+fn to_superscript(x: i64) -> String {
+    x.to_string()
+        .chars()
+        .map(|c| match c {
+            '0' => '⁰',
+            '1' => '¹',
+            '2' => '²',
+            '3' => '³',
+            '4' => '⁴',
+            '5' => '⁵',
+            '6' => '⁶',
+            '7' => '⁷',
+            '8' => '⁸',
+            _ => '⁹',
+        })
+        .collect()
 }
 
+#[allow(clippy::too_many_arguments)]
 fn fmt_binary(
     f: &mut fmt::Formatter,
     x: &Rc<Expression>,
@@ -182,50 +623,241 @@ fn fmt_binary(
     precedence: i32,
     abelian: bool,
     rtl: bool,
+    style: DisplayStyle,
 ) -> fmt::Result {
     let lhs = if x.precedence() < precedence || (x.precedence() == precedence && rtl && !abelian) {
-        format!("({})", x)
+        format!("({})", x.format_with(style))
     } else {
-        format!("{}", x)
+        format!("{}", x.format_with(style))
     };
     let rhs = if y.precedence() < precedence || (y.precedence() == precedence && !rtl && !abelian) {
-        format!("({})", y)
+        format!("({})", y.format_with(style))
     } else {
-        format!("{}", y)
+        format!("{}", y.format_with(style))
     };
     write!(f, "{}{}{}", lhs, operator, rhs)
 }
 
-impl fmt::Display for Expression {
+impl Expression {
+    #[inline]
+    pub fn format_with(&self, style: DisplayStyle) -> StyledExpression<'_> {
+        StyledExpression {
+            expression: self,
+            style,
+        }
+    }
+}
+
+impl<'a> fmt::Display for StyledExpression<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
+        self.fmt_node(f)?;
+        if self.style == DisplayStyle::Annotated && !self.expression.is_number() {
+            if let Some(value) = self.expression.evaluate() {
+                write!(f, " [{}]", value)
+            } else {
+                write!(f, " [?]")
+            }
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<'a> StyledExpression<'a> {
+    fn fmt_node(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let style = self.style;
+        let expression = self.expression;
+        match expression {
             Expression::Number(x) => write!(f, "{}", x),
+            Expression::Decimal(x) => write!(f, ".{}", x),
+            Expression::RepeatingDecimal(x) => write!(f, ".{}...", x),
             Expression::Negate(x) => {
                 if x.is_add() || x.is_subtract() {
-                    write!(f, "-({})", x)
+                    write!(f, "-({})", x.format_with(style))
                 } else {
-                    write!(f, "-{}", x)
+                    write!(f, "-{}", x.format_with(style))
                 }
             }
-            Expression::Add(x, y) => fmt_binary(f, x, y, "+", self.precedence(), true, false),
-            Expression::Subtract(x, y) => fmt_binary(f, x, y, "-", self.precedence(), false, false),
-            Expression::Multiply(x, y) => fmt_binary(f, x, y, "*", self.precedence(), true, false),
-            Expression::Divide(x, y) => fmt_binary(f, x, y, "/", self.precedence(), false, false),
-            Expression::Power(x, y) => fmt_binary(f, x, y, "^", self.precedence(), false, true),
-            Expression::Sqrt(x, order) => {
-                write!(f, "{}{}{}", "sqrt(".repeat(*order), x, ")".repeat(*order))
+            Expression::Add(x, y) => {
+                fmt_binary(f, x, y, "+", expression.precedence(), true, false, style)
+            }
+            Expression::Subtract(x, y) => {
+                fmt_binary(f, x, y, "-", expression.precedence(), false, false, style)
+            }
+            Expression::Multiply(x, y) => {
+                let operator = if style == DisplayStyle::Unicode {
+                    "×"
+                } else {
+                    "*"
+                };
+                fmt_binary(
+                    f,
+                    x,
+                    y,
+                    operator,
+                    expression.precedence(),
+                    true,
+                    false,
+                    style,
+                )
+            }
+            Expression::Divide(x, y) => {
+                if style == DisplayStyle::FallingFactorial {
+                    if let (Some(x0), Some(y0)) = (x.to_factorial(), y.to_factorial()) {
+                        let m = x0.evaluate().and_then(|m| m.to_int());
+                        let n = y0.evaluate().and_then(|n| n.to_int());
+                        if let (Some(m), Some(n)) = (m, n) {
+                            if m > n {
+                                return write!(f, "({})_{}", x0.format_with(style), m - n);
+                            }
+                        }
+                    }
+                }
+                fmt_binary(f, x, y, "/", expression.precedence(), false, false, style)
+            }
+            Expression::Power(x, y) => match (style, y.as_ref()) {
+                (DisplayStyle::Unicode, Expression::Number(exponent)) if *exponent >= 0 => {
+                    if x.precedence() <= expression.precedence() || x.is_sqrt() {
+                        write!(f, "({})", x.format_with(style))?;
+                    } else {
+                        write!(f, "{}", x.format_with(style))?;
+                    }
+                    write!(f, "{}", to_superscript(*exponent))
+                }
+                _ => fmt_binary(f, x, y, "^", expression.precedence(), false, true, style),
+            },
+            Expression::Tetration(x, y) => {
+                fmt_binary(f, x, y, "^^", expression.precedence(), false, true, style)
             }
+            Expression::Sqrt(x, order) => match style {
+                DisplayStyle::CompactUnaryChains if *order > 1 => {
+                    write!(f, "sqrt^{}({})", order, x.format_with(style))
+                }
+                DisplayStyle::Unicode => {
+                    if x.is_atomic() {
+                        write!(f, "{}{}", "√".repeat(*order), x.format_with(style))
+                    } else {
+                        write!(f, "{}({})", "√".repeat(*order), x.format_with(style))
+                    }
+                }
+                _ => write!(
+                    f,
+                    "{}{}{}",
+                    "sqrt(".repeat(*order),
+                    x.format_with(style),
+                    ")".repeat(*order)
+                ),
+            },
             Expression::Factorial(x) => {
-                if x.is_number() {
-                    write!(f, "{}!", x)
+                if x.is_literal() {
+                    write!(f, "{}!", x.format_with(style))
+                } else {
+                    write!(f, "({})!", x.format_with(style))
+                }
+            }
+            Expression::Average(x, y) => {
+                write!(f, "avg({},{})", x.format_with(style), y.format_with(style))
+            }
+            Expression::Round(x) => write!(f, "round({})", x.format_with(style)),
+            Expression::Subfactorial(x) => {
+                if x.is_atomic() {
+                    write!(f, "!{}", x.format_with(style))
+                } else {
+                    write!(f, "!({})", x.format_with(style))
+                }
+            }
+            Expression::NthPrime(x) => write!(f, "prime({})", x.format_with(style)),
+            Expression::PrimePi(x) => write!(f, "pi({})", x.format_with(style)),
+            Expression::DoubleFactorial(x) => {
+                if x.is_literal() {
+                    write!(f, "{}!!", x.format_with(style))
+                } else {
+                    write!(f, "({})!!", x.format_with(style))
+                }
+            }
+            Expression::Root(x, 3) => write!(f, "cuberoot({})", x.format_with(style)),
+            Expression::Root(x, degree) => write!(f, "root({},{})", degree, x.format_with(style)),
+            Expression::Percent(x) => {
+                if x.is_literal() || x.is_percent() {
+                    write!(f, "{}%", x.format_with(style))
                 } else {
-                    write!(f, "({})!", x)
+                    write!(f, "({})%", x.format_with(style))
                 }
             }
+            Expression::Log(x, _) => write!(f, "log({})", x.format_with(style)),
         }
     }
 }
 
+impl Expression {
+    fn cmp_structure(&self, other: &Self) -> Ordering {
+        self.op_kind()
+            .cmp(&other.op_kind())
+            .then_with(|| match (self, other) {
+                (Expression::Number(x), Expression::Number(y))
+                | (Expression::Decimal(x), Expression::Decimal(y))
+                | (Expression::RepeatingDecimal(x), Expression::RepeatingDecimal(y)) => x.cmp(y),
+                (Expression::Negate(x), Expression::Negate(y))
+                | (Expression::Factorial(x), Expression::Factorial(y))
+                | (Expression::Round(x), Expression::Round(y))
+                | (Expression::Subfactorial(x), Expression::Subfactorial(y))
+                | (Expression::NthPrime(x), Expression::NthPrime(y))
+                | (Expression::PrimePi(x), Expression::PrimePi(y))
+                | (Expression::DoubleFactorial(x), Expression::DoubleFactorial(y))
+                | (Expression::Percent(x), Expression::Percent(y)) => x.cmp_structure(y),
+                (Expression::Add(x0, x1), Expression::Add(y0, y1))
+                | (Expression::Multiply(x0, x1), Expression::Multiply(y0, y1))
+                | (Expression::Average(x0, x1), Expression::Average(y0, y1))
+                | (Expression::Subtract(x0, x1), Expression::Subtract(y0, y1))
+                | (Expression::Divide(x0, x1), Expression::Divide(y0, y1))
+                | (Expression::Power(x0, x1), Expression::Power(y0, y1))
+                | (Expression::Tetration(x0, x1), Expression::Tetration(y0, y1)) => {
+                    x0.cmp_structure(y0).then_with(|| x1.cmp_structure(y1))
+                }
+                (Expression::Sqrt(x, m), Expression::Sqrt(y, n)) => {
+                    m.cmp(n).then_with(|| x.cmp_structure(y))
+                }
+                (Expression::Root(x, m), Expression::Root(y, n)) => {
+                    m.cmp(n).then_with(|| x.cmp_structure(y))
+                }
+                (Expression::Log(x, m), Expression::Log(y, n)) => {
+                    m.cmp(n).then_with(|| x.cmp_structure(y))
+                }
+                _ => unreachable!(),
+            })
+    }
+}
+
+impl PartialOrd for Expression {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Expression {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.operator_count()
+            .cmp(&other.operator_count())
+            .then_with(|| self.depth().cmp(&other.depth()))
+            .then_with(|| self.cmp_structure(other))
+    }
+}
+
+impl fmt::Display for Expression {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.format_with(DisplayStyle::Standard), f)
+    }
+}
+
+impl fmt::Debug for Expression {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
 fn add_latex_parens(x: String) -> String {
     "\\left(".to_string() + &x + "\\right)"
 }
@@ -255,6 +887,8 @@ impl Expression {
     pub fn to_latex_string(&self) -> String {
         match self {
             Expression::Number(x) => x.to_string(),
+            Expression::Decimal(x) => format!(".{}", x),
+            Expression::RepeatingDecimal(x) => format!(".\\overline{{{}}}", x),
             Expression::Negate(x) => {
                 if x.is_add() || x.is_subtract() {
                     "-".to_string() + &add_latex_parens(x.to_latex_string())
@@ -267,7 +901,7 @@ impl Expression {
                 fmt_latex_binary(x, y, "-", self.precedence(), false, false)
             }
             Expression::Multiply(x, y) => {
-                fmt_latex_binary(x, y, "\\times", self.precedence(), true, false)
+                fmt_latex_binary(x, y, "\\cdot ", self.precedence(), true, false)
             }
             Expression::Divide(x, y) => format!(
                 "\\frac{{{}}}{{{}}}",
@@ -283,6 +917,19 @@ impl Expression {
                 },
                 y.to_latex_string()
             ),
+            Expression::Tetration(x, y) => format!(
+                "{}\\uparrow\\uparrow {}",
+                if x.is_number() {
+                    x.to_latex_string()
+                } else {
+                    add_latex_parens(x.to_latex_string())
+                },
+                if y.is_number() {
+                    y.to_latex_string()
+                } else {
+                    add_latex_parens(y.to_latex_string())
+                }
+            ),
             Expression::Sqrt(x, order) => {
                 "\\sqrt{".repeat(*order)
                     + x.to_latex_string().as_str()
@@ -295,6 +942,46 @@ impl Expression {
                     add_latex_parens(x.to_latex_string()) + "!"
                 }
             }
+            Expression::Average(x, y) => format!(
+                "\\operatorname{{avg}}{}",
+                add_latex_parens(x.to_latex_string() + "," + y.to_latex_string().as_str())
+            ),
+            Expression::Round(x) => format!(
+                "\\operatorname{{round}}{}",
+                add_latex_parens(x.to_latex_string())
+            ),
+            Expression::Subfactorial(x) => {
+                if x.is_number() {
+                    "!".to_string() + &x.to_latex_string()
+                } else {
+                    "!".to_string() + &add_latex_parens(x.to_latex_string())
+                }
+            }
+            Expression::NthPrime(x) => format!(
+                "\\operatorname{{prime}}{}",
+                add_latex_parens(x.to_latex_string())
+            ),
+            Expression::PrimePi(x) => format!("\\pi{}", add_latex_parens(x.to_latex_string())),
+            Expression::DoubleFactorial(x) => {
+                if x.is_number() {
+                    x.to_latex_string() + "!!"
+                } else {
+                    add_latex_parens(x.to_latex_string()) + "!!"
+                }
+            }
+            Expression::Root(x, degree) => format!("\\sqrt[{}]{{{}}}", degree, x.to_latex_string()),
+            Expression::Percent(x) => {
+                if x.is_number() || x.is_percent() {
+                    x.to_latex_string() + "\\%"
+                } else {
+                    add_latex_parens(x.to_latex_string()) + "\\%"
+                }
+            }
+            Expression::Log(x, base) => format!(
+                "\\log_{{{}}}{}",
+                base,
+                add_latex_parens(x.to_latex_string())
+            ),
         }
     }
 
@@ -302,6 +989,14 @@ impl Expression {
         Rc::new(Expression::Number(x))
     }
 
+    pub fn from_decimal(x: i64) -> Rc<Expression> {
+        Rc::new(Expression::Decimal(x))
+    }
+
+    pub fn from_repeating_decimal(x: i64) -> Rc<Expression> {
+        Rc::new(Expression::RepeatingDecimal(x))
+    }
+
     pub fn from_negate(x: Rc<Expression>) -> Rc<Expression> {
         if let Some((y, z)) = x.to_subtract() {
             Rc::new(Expression::Subtract(z.clone(), y.clone()))
@@ -443,4 +1138,157 @@ impl Expression {
     pub fn from_factorial(x: Rc<Expression>) -> Rc<Expression> {
         Rc::new(Expression::Factorial(x))
     }
+
+    pub fn from_average(x: Rc<Expression>, y: Rc<Expression>) -> Rc<Expression> {
+        Rc::new(Expression::Average(x, y))
+    }
+
+    pub fn from_round(x: Rc<Expression>) -> Rc<Expression> {
+        Rc::new(Expression::Round(x))
+    }
+
+    pub fn from_subfactorial(x: Rc<Expression>) -> Rc<Expression> {
+        Rc::new(Expression::Subfactorial(x))
+    }
+
+    pub fn from_nth_prime(x: Rc<Expression>) -> Rc<Expression> {
+        Rc::new(Expression::NthPrime(x))
+    }
+
+    pub fn from_prime_pi(x: Rc<Expression>) -> Rc<Expression> {
+        Rc::new(Expression::PrimePi(x))
+    }
+
+    pub fn from_double_factorial(x: Rc<Expression>) -> Rc<Expression> {
+        Rc::new(Expression::DoubleFactorial(x))
+    }
+
+    pub fn from_root(x: Rc<Expression>, degree: u32) -> Rc<Expression> {
+        Rc::new(Expression::Root(x, degree))
+    }
+
+    pub fn from_percent(x: Rc<Expression>) -> Rc<Expression> {
+        Rc::new(Expression::Percent(x))
+    }
+
+    pub fn from_log(x: Rc<Expression>, base: i64) -> Rc<Expression> {
+        Rc::new(Expression::Log(x, base))
+    }
+
+    pub fn from_tetration(x: Rc<Expression>, y: Rc<Expression>) -> Rc<Expression> {
+        Rc::new(Expression::Tetration(x, y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str, n: i64) -> Rc<Expression> {
+        Expression::parse(s, n).unwrap()
+    }
+
+    #[test]
+    fn displays_minimal_parentheses() {
+        let cases = [
+            ("(7+7)+7", "7+7+7"),
+            ("7-(7-7)", "7-(7-7)"),
+            ("(7-7)-7", "7-7-7"),
+            ("(7/7)/7", "7/7/7"),
+            ("7/(7/7)", "7/(7/7)"),
+            ("7^(7^7)", "7^7^7"),
+            ("(7^7)^7", "(7^7)^7"),
+            ("7*(7^7)", "7*7^7"),
+            ("(7*7)^7", "(7*7)^7"),
+            ("(7+7)!", "(7+7)!"),
+            ("7!/7", "7!/7"),
+        ];
+        for (input, expected) in cases.iter() {
+            assert_eq!(parse(input, 7).to_string(), *expected);
+        }
+    }
+
+    #[test]
+    fn postfix_operators_keep_parentheses_around_calls() {
+        assert_eq!(parse("sqrt(8+8/8)!", 8).to_string(), "(sqrt(8+8/8))!");
+        assert_eq!(parse("(sqrt(8+8/8))!", 8).to_string(), "(sqrt(8+8/8))!");
+        assert_eq!(parse("8!", 8).to_string(), "8!");
+    }
+
+    #[test]
+    fn latex_snapshots() {
+        assert_eq!(
+            parse("(5+5)!/5!", 5).to_latex_string(),
+            "\\frac{\\left(5+5\\right)!}{5!}"
+        );
+        assert_eq!(
+            parse("sqrt(sqrt(sqrt(2)))*2", 2).to_latex_string(),
+            "\\sqrt{\\sqrt{\\sqrt{2}}}\\cdot 2"
+        );
+        assert_eq!(
+            parse("sqrt(sqrt(2))*(2+2)", 2).to_latex_string(),
+            "\\sqrt{\\sqrt{2}}\\cdot \\left(2+2\\right)"
+        );
+        assert_eq!(parse("-sqrt(3)", 3).to_latex_string(), "-\\sqrt{3}");
+    }
+
+    #[test]
+    fn unicode_snapshots() {
+        let cases = [
+            ("sqrt(sqrt(sqrt(2)))*2", "sqrt(sqrt(sqrt(2)))*2", "√√√2×2"),
+            ("sqrt(2+2)*22", "sqrt(2+2)*22", "√(2+2)×22"),
+            ("sqrt(2)^22", "sqrt(2)^22", "(√2)²²"),
+            ("(2+2)^2", "(2+2)^2", "(2+2)²"),
+            ("2^(2+2)", "2^(2+2)", "2^(2+2)"),
+        ];
+        for (input, ascii, unicode) in cases.iter() {
+            let expression = parse(input, 2);
+            assert_eq!(expression.to_string(), *ascii);
+            assert_eq!(
+                expression.format_with(DisplayStyle::Unicode).to_string(),
+                *unicode
+            );
+        }
+    }
+
+    #[test]
+    fn costs_leaves_in_another_base() {
+        let expression =
+            Expression::from_divide(Expression::from_number(255), Expression::from_number(5));
+        assert_eq!(expression.cost_in_base(10), 4);
+        assert_eq!(expression.cost_in_base(16), 3);
+        assert_eq!(expression.cost_in_base(2), 11);
+        assert_eq!(parse("sqrt(sqrt(2))*(2+2)", 2).cost_in_base(10), 3);
+    }
+
+    #[test]
+    fn sorts_by_complexity() {
+        let four = || Rc::new(Expression::Number(4));
+        let add = |x, y| Rc::new(Expression::Add(x, y));
+        let expected = vec![
+            four(),
+            Rc::new(Expression::Number(44)),
+            add(four(), four()),
+            Rc::new(Expression::Multiply(four(), four())),
+            Rc::new(Expression::Sqrt(four(), 1)),
+            add(four(), add(four(), four())),
+            add(add(four(), four()), four()),
+            Rc::new(Expression::Multiply(add(four(), four()), four())),
+            Rc::new(Expression::Sqrt(Rc::new(Expression::Sqrt(four(), 1)), 1)),
+            Rc::new(Expression::Factorial(add(four(), four()))),
+        ];
+        let mut sorted = expected.clone();
+        sorted.reverse();
+        sorted.swap(2, 7);
+        sorted.sort();
+        assert_eq!(sorted, expected);
+        for pair in sorted.windows(2) {
+            assert_eq!(pair[0].cmp(&pair[1]), Ordering::Less);
+            assert_eq!(pair[1].cmp(&pair[0]), Ordering::Greater);
+        }
+        assert_eq!(
+            add(four(), four()).cmp(&add(four(), four())),
+            Ordering::Equal
+        );
+    }
 }
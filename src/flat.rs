@@ -0,0 +1,191 @@
+use crate::{Expression, OpKind, Rc};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct FlatNode {
+    pub op_tag: u8,
+    pub value: i64,
+    pub left: i32,
+    pub right: i32,
+}
+
+const OP_KINDS: [OpKind; 21] = [
+    OpKind::Number,
+    OpKind::Negate,
+    OpKind::Add,
+    OpKind::Subtract,
+    OpKind::Multiply,
+    OpKind::Divide,
+    OpKind::Power,
+    OpKind::Sqrt,
+    OpKind::Factorial,
+    OpKind::Average,
+    OpKind::Round,
+    OpKind::Subfactorial,
+    OpKind::NthPrime,
+    OpKind::PrimePi,
+    OpKind::DoubleFactorial,
+    OpKind::Root,
+    OpKind::Decimal,
+    OpKind::RepeatingDecimal,
+    OpKind::Percent,
+    OpKind::Log,
+    OpKind::Tetration,
+];
+
+impl Expression {
+    pub fn to_flat(&self) -> Vec<FlatNode> {
+        let mut nodes = vec![];
+        self.push_flat(&mut nodes);
+        nodes
+    }
+
+    fn push_flat(&self, nodes: &mut Vec<FlatNode>) -> i32 {
+        let index = nodes.len();
+        nodes.push(FlatNode {
+            op_tag: self.op_kind() as u8,
+            value: 0,
+            left: -1,
+            right: -1,
+        });
+        let (value, left, right) = match self {
+            Expression::Number(x) | Expression::Decimal(x) | Expression::RepeatingDecimal(x) => {
+                (*x, -1, -1)
+            }
+            Expression::Negate(x)
+            | Expression::Factorial(x)
+            | Expression::Round(x)
+            | Expression::Subfactorial(x)
+            | Expression::NthPrime(x)
+            | Expression::PrimePi(x)
+            | Expression::DoubleFactorial(x)
+            | Expression::Percent(x) => (0, x.push_flat(nodes), -1),
+            Expression::Sqrt(x, order) => (*order as i64, x.push_flat(nodes), -1),
+            Expression::Root(x, degree) => (*degree as i64, x.push_flat(nodes), -1),
+            Expression::Log(x, base) => (*base, x.push_flat(nodes), -1),
+            Expression::Add(x, y)
+            | Expression::Subtract(x, y)
+            | Expression::Multiply(x, y)
+            | Expression::Divide(x, y)
+            | Expression::Power(x, y)
+            | Expression::Tetration(x, y)
+            | Expression::Average(x, y) => {
+                let left = x.push_flat(nodes);
+                (0, left, y.push_flat(nodes))
+            }
+        };
+        nodes[index].value = value;
+        nodes[index].left = left;
+        nodes[index].right = right;
+        index as i32
+    }
+
+    pub fn from_flat(nodes: &[FlatNode]) -> Option<Rc<Expression>> {
+        from_flat_node(nodes, 0, nodes.len())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Expression> for Vec<FlatNode> {
+    fn from(expression: Expression) -> Self {
+        expression.to_flat()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<Vec<FlatNode>> for Expression {
+    type Error = &'static str;
+
+    fn try_from(nodes: Vec<FlatNode>) -> Result<Self, Self::Error> {
+        let expression = Expression::from_flat(&nodes).ok_or("malformed expression")?;
+        Ok(Rc::try_unwrap(expression).unwrap_or_else(|expression| (*expression).clone()))
+    }
+}
+
+fn from_flat_node(nodes: &[FlatNode], index: i32, depth: usize) -> Option<Rc<Expression>> {
+    if index < 0 || depth == 0 {
+        return None;
+    }
+    let node = nodes.get(index as usize)?;
+    let child = |index| from_flat_node(nodes, index, depth - 1);
+    let expression = match OP_KINDS.get(node.op_tag as usize)? {
+        OpKind::Number => Expression::Number(node.value),
+        OpKind::Decimal => Expression::Decimal(node.value),
+        OpKind::RepeatingDecimal => Expression::RepeatingDecimal(node.value),
+        OpKind::Negate => Expression::Negate(child(node.left)?),
+        OpKind::Add => Expression::Add(child(node.left)?, child(node.right)?),
+        OpKind::Subtract => Expression::Subtract(child(node.left)?, child(node.right)?),
+        OpKind::Multiply => Expression::Multiply(child(node.left)?, child(node.right)?),
+        OpKind::Divide => Expression::Divide(child(node.left)?, child(node.right)?),
+        OpKind::Power => Expression::Power(child(node.left)?, child(node.right)?),
+        OpKind::Sqrt => {
+            let order = usize::try_from(node.value)
+                .ok()
+                .filter(|order| *order >= 1)?;
+            Expression::Sqrt(child(node.left)?, order)
+        }
+        OpKind::Factorial => Expression::Factorial(child(node.left)?),
+        OpKind::Average => Expression::Average(child(node.left)?, child(node.right)?),
+        OpKind::Round => Expression::Round(child(node.left)?),
+        OpKind::Subfactorial => Expression::Subfactorial(child(node.left)?),
+        OpKind::NthPrime => Expression::NthPrime(child(node.left)?),
+        OpKind::PrimePi => Expression::PrimePi(child(node.left)?),
+        OpKind::DoubleFactorial => Expression::DoubleFactorial(child(node.left)?),
+        OpKind::Root => Expression::Root(child(node.left)?, u32::try_from(node.value).ok()?),
+        OpKind::Percent => Expression::Percent(child(node.left)?),
+        OpKind::Log => Expression::Log(child(node.left)?, node.value),
+        OpKind::Tetration => Expression::Tetration(child(node.left)?, child(node.right)?),
+    };
+    Some(Rc::new(expression))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reconstructs_from_flat() {
+        for s in [
+            "4",
+            "-(4!!)",
+            "sqrt(sqrt(4))^(4+4)",
+            "(4/4+4)!-4%",
+            "cuberoot(4*4*4)+log(4*4)",
+            "4^^(4-4/4)",
+        ] {
+            let expression = Expression::parse(s, 4).unwrap();
+            let flat = expression.to_flat();
+            assert_eq!(flat[0].op_tag, expression.op_kind() as u8);
+            assert_eq!(Expression::from_flat(&flat), Some(expression), "{}", s);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_nodes() {
+        let number = FlatNode {
+            op_tag: OpKind::Number as u8,
+            value: 4,
+            left: -1,
+            right: -1,
+        };
+        let cyclic = FlatNode {
+            op_tag: OpKind::Negate as u8,
+            value: 0,
+            left: 0,
+            right: -1,
+        };
+        let dangling = FlatNode {
+            op_tag: OpKind::Add as u8,
+            value: 0,
+            left: 1,
+            right: 2,
+        };
+        assert_eq!(Expression::from_flat(&[]), None);
+        assert_eq!(Expression::from_flat(&[cyclic]), None);
+        assert_eq!(Expression::from_flat(&[dangling, number]), None);
+    }
+}
@@ -0,0 +1,338 @@
+use crate::Number;
+use num::rational::Rational64;
+use num::traits::{CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, Inv};
+use num::{Num, One, Signed, Zero};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Rem, Sub};
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct GaussianRational {
+    real_part: Rational64,
+    imaginary_part: Rational64,
+}
+
+pub struct ParseGaussianRationalError {}
+
+impl GaussianRational {
+    #[inline]
+    pub fn new(real_part: Rational64, imaginary_part: Rational64) -> Self {
+        Self {
+            real_part,
+            imaginary_part,
+        }
+    }
+
+    /// What the arithmetic operators return on overflow or division by zero.
+    #[inline]
+    pub fn overflow() -> Self {
+        Self::new(i64::MAX.into(), Rational64::zero())
+    }
+
+    #[inline]
+    pub fn i() -> Self {
+        Self::new(Rational64::zero(), Rational64::one())
+    }
+
+    #[inline]
+    pub fn real_part(&self) -> Rational64 {
+        self.real_part
+    }
+
+    #[inline]
+    pub fn imaginary_part(&self) -> Rational64 {
+        self.imaginary_part
+    }
+
+    #[inline]
+    pub fn is_real(&self) -> bool {
+        self.imaginary_part.is_zero()
+    }
+
+    pub fn try_sqrt(&self) -> Option<Self> {
+        if self.is_real() {
+            return if self.real_part.is_negative() {
+                Some(Self::new(Rational64::zero(), (-self.real_part).try_sqrt()?))
+            } else {
+                Some(Self::new(self.real_part.try_sqrt()?, Rational64::zero()))
+            };
+        }
+        let norm = self
+            .real_part
+            .checked_mul(&self.real_part)?
+            .checked_add(&self.imaginary_part.checked_mul(&self.imaginary_part)?)?;
+        let modulus = norm.try_sqrt()?;
+        let two = Rational64::from(2);
+        let real_part = modulus
+            .checked_add(&self.real_part)?
+            .checked_div(&two)?
+            .try_sqrt()?;
+        let imaginary_part = modulus
+            .checked_sub(&self.real_part)?
+            .checked_div(&two)?
+            .try_sqrt()?;
+        Some(Self::new(
+            real_part,
+            if self.imaginary_part.is_negative() {
+                -imaginary_part
+            } else {
+                imaginary_part
+            },
+        ))
+    }
+
+    pub fn try_nth_root(&self, n: u32) -> Option<Self> {
+        if n == 0 {
+            None
+        } else if n == 1 || self.is_zero() {
+            Some(*self)
+        } else if n.is_multiple_of(2) {
+            self.try_sqrt()?.try_nth_root(n / 2)
+        } else if self.is_real() {
+            Some(self.real_part.try_nth_root(n)?.into())
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for GaussianRational {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_real() {
+            return write!(f, "{}", self.real_part);
+        }
+        if !self.real_part.is_zero() {
+            write!(f, "{}", self.real_part)?;
+            if self.imaginary_part.is_positive() {
+                write!(f, "+")?;
+            }
+        }
+        if self.imaginary_part.is_one() {
+            write!(f, "i")
+        } else if (-self.imaginary_part).is_one() {
+            write!(f, "-i")
+        } else {
+            write!(f, "{}*i", self.imaginary_part)
+        }
+    }
+}
+
+impl From<i64> for GaussianRational {
+    #[inline]
+    fn from(x: i64) -> Self {
+        Self::new(x.into(), Rational64::zero())
+    }
+}
+
+impl From<Rational64> for GaussianRational {
+    #[inline]
+    fn from(x: Rational64) -> Self {
+        Self::new(x, Rational64::zero())
+    }
+}
+
+impl Number for GaussianRational {
+    #[inline]
+    fn to_int(self) -> Option<i64> {
+        if self.is_real() {
+            self.real_part.to_int()
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn is_int(self) -> bool {
+        self.is_real() && self.real_part.is_integer()
+    }
+
+    #[inline]
+    fn is_rational(self) -> bool {
+        self.is_real()
+    }
+
+    #[inline]
+    fn try_sqrt(self) -> Option<Self> {
+        GaussianRational::try_sqrt(&self)
+    }
+
+    #[inline]
+    fn try_nth_root(self, n: u32) -> Option<Self> {
+        GaussianRational::try_nth_root(&self, n)
+    }
+
+    #[inline]
+    fn to_f64(self) -> f64 {
+        if self.is_real() {
+            self.real_part.to_f64()
+        } else {
+            f64::NAN
+        }
+    }
+}
+
+impl Num for GaussianRational {
+    type FromStrRadixErr = ParseGaussianRationalError;
+
+    fn from_str_radix(_str: &str, _radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+        Err(ParseGaussianRationalError {})
+    }
+}
+
+impl Zero for GaussianRational {
+    #[inline]
+    fn zero() -> Self {
+        Self::new(Rational64::zero(), Rational64::zero())
+    }
+
+    #[inline]
+    fn is_zero(&self) -> bool {
+        self.real_part.is_zero() && self.imaginary_part.is_zero()
+    }
+}
+
+impl One for GaussianRational {
+    #[inline]
+    fn one() -> Self {
+        Self::new(Rational64::one(), Rational64::zero())
+    }
+
+    #[inline]
+    fn is_one(&self) -> bool {
+        self.real_part.is_one() && self.imaginary_part.is_zero()
+    }
+}
+
+#[opimps::impl_uni_ops(Neg)]
+#[inline]
+fn neg(self: GaussianRational) -> GaussianRational {
+    GaussianRational::new(-self.real_part, -self.imaginary_part)
+}
+
+impl Signed for GaussianRational {
+    fn abs(&self) -> Self {
+        if self.is_negative() {
+            -self
+        } else {
+            *self
+        }
+    }
+
+    fn abs_sub(&self, other: &Self) -> Self {
+        (self - other).abs()
+    }
+
+    fn signum(&self) -> Self {
+        if self.is_zero() {
+            Self::zero()
+        } else if self.is_negative() {
+            -Self::one()
+        } else {
+            Self::one()
+        }
+    }
+
+    fn is_positive(&self) -> bool {
+        self.real_part.is_positive()
+            || self.real_part.is_zero() && self.imaginary_part.is_positive()
+    }
+
+    fn is_negative(&self) -> bool {
+        self.real_part.is_negative()
+            || self.real_part.is_zero() && self.imaginary_part.is_negative()
+    }
+}
+
+// The operators below saturate to `GaussianRational::overflow()` instead of panicking when
+// a part overflows or the divisor is zero; the solver goes through the `Checked*` impls.
+#[opimps::impl_ops(Add)]
+#[inline]
+fn add(self: GaussianRational, rhs: GaussianRational) -> GaussianRational {
+    self.checked_add(&rhs)
+        .unwrap_or_else(GaussianRational::overflow)
+}
+
+#[opimps::impl_ops(Sub)]
+#[inline]
+fn sub(self: GaussianRational, rhs: GaussianRational) -> GaussianRational {
+    self.checked_sub(&rhs)
+        .unwrap_or_else(GaussianRational::overflow)
+}
+
+#[opimps::impl_ops(Mul)]
+#[inline]
+fn mul(self: GaussianRational, rhs: GaussianRational) -> GaussianRational {
+    self.checked_mul(&rhs)
+        .unwrap_or_else(GaussianRational::overflow)
+}
+
+impl Inv for GaussianRational {
+    type Output = Option<GaussianRational>;
+
+    #[inline]
+    fn inv(self) -> Option<GaussianRational> {
+        GaussianRational::one().checked_div(&self)
+    }
+}
+
+#[opimps::impl_ops(Div)]
+#[inline]
+fn div(self: GaussianRational, rhs: GaussianRational) -> GaussianRational {
+    self.checked_div(&rhs)
+        .unwrap_or_else(GaussianRational::overflow)
+}
+
+// Every nonzero Gaussian rational divides every other one, so the remainder is always zero.
+// `Rem` only exists because `Num` requires it.
+#[opimps::impl_ops(Rem)]
+#[inline]
+fn rem(self: GaussianRational, _rhs: GaussianRational) -> GaussianRational {
+    GaussianRational::zero()
+}
+
+impl CheckedAdd for GaussianRational {
+    fn checked_add(&self, rhs: &Self) -> Option<Self> {
+        Some(Self::new(
+            self.real_part.checked_add(&rhs.real_part)?,
+            self.imaginary_part.checked_add(&rhs.imaginary_part)?,
+        ))
+    }
+}
+
+impl CheckedSub for GaussianRational {
+    fn checked_sub(&self, rhs: &Self) -> Option<Self> {
+        Some(Self::new(
+            self.real_part.checked_sub(&rhs.real_part)?,
+            self.imaginary_part.checked_sub(&rhs.imaginary_part)?,
+        ))
+    }
+}
+
+impl CheckedMul for GaussianRational {
+    fn checked_mul(&self, rhs: &Self) -> Option<Self> {
+        Some(Self::new(
+            self.real_part
+                .checked_mul(&rhs.real_part)?
+                .checked_sub(&self.imaginary_part.checked_mul(&rhs.imaginary_part)?)?,
+            self.real_part
+                .checked_mul(&rhs.imaginary_part)?
+                .checked_add(&self.imaginary_part.checked_mul(&rhs.real_part)?)?,
+        ))
+    }
+}
+
+impl CheckedDiv for GaussianRational {
+    fn checked_div(&self, rhs: &Self) -> Option<Self> {
+        let norm = rhs
+            .real_part
+            .checked_mul(&rhs.real_part)?
+            .checked_add(&rhs.imaginary_part.checked_mul(&rhs.imaginary_part)?)?;
+        let conjugate = Self::new(
+            rhs.real_part.checked_div(&norm)?,
+            -rhs.imaginary_part.checked_div(&norm)?,
+        );
+        self.checked_mul(&conjugate)
+    }
+}
@@ -0,0 +1,88 @@
+use crate::{Expression, OpKind, ProgressiveSolver};
+use serde_json::{json, Map, Value};
+
+fn op_name(op_kind: OpKind) -> &'static str {
+    match op_kind {
+        OpKind::Number => "number",
+        OpKind::Negate => "negate",
+        OpKind::Add => "add",
+        OpKind::Subtract => "subtract",
+        OpKind::Multiply => "multiply",
+        OpKind::Divide => "divide",
+        OpKind::Power => "power",
+        OpKind::Sqrt => "sqrt",
+        OpKind::Factorial => "factorial",
+        OpKind::Average => "average",
+        OpKind::Round => "round",
+        OpKind::Subfactorial => "subfactorial",
+        OpKind::NthPrime => "nth_prime",
+        OpKind::PrimePi => "prime_pi",
+        OpKind::DoubleFactorial => "double_factorial",
+        OpKind::Root => "root",
+        OpKind::Decimal => "decimal",
+        OpKind::RepeatingDecimal => "repeating_decimal",
+        OpKind::Percent => "percent",
+        OpKind::Log => "log",
+        OpKind::Tetration => "tetration",
+    }
+}
+
+impl Expression {
+    pub fn to_json(&self) -> Value {
+        let mut node = Map::new();
+        node.insert("op".to_string(), op_name(self.op_kind()).into());
+        match self {
+            Expression::Number(_) | Expression::Decimal(_) | Expression::RepeatingDecimal(_) => {
+                let value = self.evaluate().map(|x| x.to_string());
+                node.insert("value".to_string(), value.into());
+                node.insert("display".to_string(), self.to_string().into());
+            }
+            Expression::Negate(x)
+            | Expression::Factorial(x)
+            | Expression::Round(x)
+            | Expression::Subfactorial(x)
+            | Expression::NthPrime(x)
+            | Expression::PrimePi(x)
+            | Expression::DoubleFactorial(x)
+            | Expression::Percent(x) => {
+                node.insert("operand".to_string(), x.to_json());
+            }
+            Expression::Sqrt(x, order) => {
+                node.insert("order".to_string(), (*order).into());
+                node.insert("operand".to_string(), x.to_json());
+            }
+            Expression::Root(x, degree) => {
+                node.insert("degree".to_string(), (*degree).into());
+                node.insert("operand".to_string(), x.to_json());
+            }
+            Expression::Log(x, base) => {
+                node.insert("base".to_string(), (*base).into());
+                node.insert("operand".to_string(), x.to_json());
+            }
+            Expression::Add(x, y)
+            | Expression::Subtract(x, y)
+            | Expression::Multiply(x, y)
+            | Expression::Divide(x, y)
+            | Expression::Power(x, y)
+            | Expression::Tetration(x, y)
+            | Expression::Average(x, y) => {
+                node.insert("left".to_string(), x.to_json());
+                node.insert("right".to_string(), y.to_json());
+            }
+        }
+        Value::Object(node)
+    }
+}
+
+impl ProgressiveSolver {
+    pub fn solution_json(&self) -> Option<Value> {
+        let (expression, digits) = self.solution()?;
+        Some(json!({
+            "expression": expression.to_json(),
+            "display": expression.to_string(),
+            "digits": digits,
+            "operators": expression.operator_count(),
+            "value": expression.eval_f64(),
+        }))
+    }
+}
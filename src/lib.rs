@@ -1,16 +1,43 @@
 #![feature(min_specialization)]
+mod canonical;
+mod evaluate;
 mod expression;
+mod flat;
+mod gaussian_rational;
+#[cfg(feature = "json")]
+mod json;
 mod number;
 mod number_theory;
+mod parse;
+mod progress_observer;
 mod progressive_solver;
 mod quadratic;
 mod reusable_solver;
+mod rpn;
 mod solver;
+#[cfg(feature = "serde")]
 mod wasm;
 
-pub use expression::Expression;
+pub use expression::{DisplayStyle, Domain, Expression, OpKind, StyledExpression};
+pub use flat::FlatNode;
+pub use gaussian_rational::GaussianRational;
 pub use number::Number;
-pub use progressive_solver::ProgressiveSolver;
-pub use quadratic::{IntegralQuadratic, RationalQuadratic, PRIMES};
-pub use reusable_solver::ReusableSolver;
-pub use solver::{Limits, Solver};
+pub use number_theory::is_prime;
+pub use parse::ParseExpressionError;
+pub use progress_observer::ProgressObserver;
+pub use progressive_solver::{
+    PeakStats, ProgressiveSolver, SearchOutcome, SolveReport, SolverConfig, SolverSnapshot,
+    VerifiedSolution,
+};
+pub use quadratic::{
+    IntegralQuadratic, ParseQuadraticError, RationalQuadratic, MAX_QUADRATIC_POWER, PRIMES,
+};
+pub use reusable_solver::{ReusableSolver, SolutionStream};
+pub use rpn::{ParseRpnError, Token};
+pub use solver::{AllowedOps, Inconsistency, Limits, SearchStats, Solver, SolverState};
+// Expression trees are shared through `Rc`, and through `Arc` when the parallel search moves them
+// between threads.
+#[cfg(not(feature = "parallel"))]
+pub use std::rc::Rc;
+#[cfg(feature = "parallel")]
+pub use std::sync::Arc as Rc;
@@ -1,7 +1,7 @@
 use std::env;
 use tchisla_solver::*;
 
-fn parse_problem() -> Option<(i64, i64, bool)> {
+fn parse_problem() -> Option<(i64, i128, bool, bool, Option<usize>)> {
     let args: Vec<String> = env::args().collect();
     if args.len() < 2 {
         return None;
@@ -10,8 +10,22 @@ fn parse_problem() -> Option<(i64, i64, bool)> {
         let target = args[1][..index].parse();
         let n = args[1][(index + 1)..].parse();
         if n.is_ok() && target.is_ok() {
-            let verbose = args.len() > 2 && args[2] == "--verbose";
-            Some((n.unwrap(), target.unwrap(), verbose))
+            let verbose = args[2..].iter().any(|arg| arg == "--verbose");
+            let no_concat = args[2..].iter().any(|arg| arg == "--no-concat");
+            let percent_depth = args[2..].iter().find_map(|arg| {
+                if arg == "--percent" {
+                    Some(Limits::default().max_percent_depth)
+                } else {
+                    arg.strip_prefix("--percent=")?.parse().ok()
+                }
+            });
+            Some((
+                n.unwrap(),
+                target.unwrap(),
+                verbose,
+                no_concat,
+                percent_depth,
+            ))
         } else {
             None
         }
@@ -21,8 +35,24 @@ fn parse_problem() -> Option<(i64, i64, bool)> {
 }
 
 fn main() {
-    if let Some((n, target, verbose)) = parse_problem() {
+    if let Some((n, target, verbose, no_concat, percent_depth)) = parse_problem() {
         println!("{} # {}", target, n);
+        let mut allowed_ops = AllowedOps::STANDARD;
+        if no_concat {
+            allowed_ops.remove(AllowedOps::CONCAT);
+        }
+        let integral_limits = Limits {
+            allowed_ops,
+            ..Limits::default()
+        };
+        let percent_limits = match percent_depth {
+            Some(max_percent_depth) => Limits {
+                allowed_ops: allowed_ops | AllowedOps::PERCENT,
+                max_percent_depth,
+                ..integral_limits.clone()
+            },
+            None => integral_limits.clone(),
+        };
         let mut solver = ProgressiveSolver::new(
             n,
             target,
@@ -31,17 +61,21 @@ fn main() {
                 max_digits: 48,
                 max_factorial: 20,
                 max_quadratic_power: 0,
+                ..integral_limits
             },
             Limits {
                 max_digits: 30,
                 max_factorial: 12,
                 max_quadratic_power: 0,
+                ..percent_limits.clone()
             },
             Limits {
                 max_digits: 20,
                 max_factorial: 9,
                 max_quadratic_power: if n == 7 { 3 } else { 2 },
+                ..percent_limits
             },
+            None,
         );
         solver.set_verbose(verbose);
         let mut solution_found = false;
@@ -1,13 +1,26 @@
+use crate::number_theory::{try_nth_root, try_sqrt};
+use num::integer::Roots;
 use num::rational::Rational64;
-use num::{Num, Signed};
+use num::{checked_pow, Num, Signed};
+use std::convert::TryFrom;
 use std::fmt::Display;
 use std::hash::Hash;
 use std::ops::Neg;
 
-pub trait Number: Copy + Display + Eq + Hash + Num + Signed + Neg + From<i64> {
+pub trait Number:
+    Copy + Display + Eq + Hash + Num + Signed + Neg + From<i64> + Send + Sync
+{
     fn to_int(self) -> Option<i64>;
     fn is_int(self) -> bool;
     fn is_rational(self) -> bool;
+    fn try_sqrt(self) -> Option<Self>;
+    fn try_nth_root(self, n: u32) -> Option<Self>;
+    fn to_f64(self) -> f64;
+
+    #[inline]
+    fn quadratic_power(self) -> u8 {
+        0
+    }
 }
 
 impl Number for i64 {
@@ -25,6 +38,67 @@ impl Number for i64 {
     fn is_rational(self) -> bool {
         true
     }
+
+    #[inline]
+    fn try_sqrt(self) -> Option<Self> {
+        try_sqrt(self)
+    }
+
+    #[inline]
+    fn try_nth_root(self, n: u32) -> Option<Self> {
+        try_nth_root(self, n)
+    }
+
+    #[inline]
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+impl Number for i128 {
+    #[inline]
+    fn to_int(self) -> Option<i64> {
+        i64::try_from(self).ok()
+    }
+
+    #[inline]
+    fn is_int(self) -> bool {
+        true
+    }
+
+    #[inline]
+    fn is_rational(self) -> bool {
+        true
+    }
+
+    fn try_sqrt(self) -> Option<Self> {
+        if self < 0 {
+            return None;
+        }
+        let m = Roots::sqrt(&self);
+        if m * m == self {
+            Some(m)
+        } else {
+            None
+        }
+    }
+
+    fn try_nth_root(self, n: u32) -> Option<Self> {
+        if n == 0 || self < 0 && n.is_multiple_of(2) {
+            return None;
+        }
+        let m = Roots::nth_root(&self, n);
+        if checked_pow(m, n as usize) == Some(self) {
+            Some(m)
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
 }
 
 impl Number for Rational64 {
@@ -46,4 +120,63 @@ impl Number for Rational64 {
     fn is_rational(self) -> bool {
         true
     }
+
+    fn try_sqrt(self) -> Option<Self> {
+        Some(Rational64::new_raw(
+            try_sqrt(*self.numer())?,
+            try_sqrt(*self.denom())?,
+        ))
+    }
+
+    fn try_nth_root(self, n: u32) -> Option<Self> {
+        Some(Rational64::new_raw(
+            try_nth_root(*self.numer(), n)?,
+            try_nth_root(*self.denom(), n)?,
+        ))
+    }
+
+    #[inline]
+    fn to_f64(self) -> f64 {
+        *self.numer() as f64 / *self.denom() as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RationalQuadratic;
+
+    #[test]
+    fn integer_roots_must_be_exact() {
+        assert_eq!(Number::try_sqrt(49i64), Some(7));
+        assert_eq!(Number::try_sqrt(50i64), None);
+        assert_eq!(Number::try_sqrt(-4i64), None);
+        assert_eq!(Number::try_nth_root(-27i64, 3), Some(-3));
+        assert_eq!(Number::try_nth_root(-16i64, 4), None);
+        let big = 1i128 << 100;
+        assert_eq!(Number::try_sqrt(big), Some(1 << 50));
+        assert_eq!(Number::try_sqrt(big + 1), None);
+        assert_eq!(Number::try_nth_root(big, 4), Some(1 << 25));
+        assert_eq!(Number::try_nth_root(-big, 5), Some(-(1 << 20)));
+        assert_eq!(Number::try_nth_root(-big, 2), None);
+    }
+
+    #[test]
+    fn rational_roots_must_be_exact() {
+        let x = Rational64::new;
+        assert_eq!(Number::try_sqrt(x(9, 4)), Some(x(3, 2)));
+        assert_eq!(Number::try_sqrt(x(2, 9)), None);
+        assert_eq!(Number::try_nth_root(x(-8, 27), 3), Some(x(-2, 3)));
+        assert_eq!(Number::try_nth_root(x(16, 81), 4), Some(x(2, 3)));
+        assert_eq!(Number::try_nth_root(x(-16, 81), 4), None);
+    }
+
+    #[test]
+    fn only_quadratics_report_a_quadratic_power() {
+        assert_eq!(Number::quadratic_power(4i128), 0);
+        assert_eq!(Number::quadratic_power(Rational64::new(1, 2)), 0);
+        let sqrt_2 = RationalQuadratic::from(2).try_sqrt().unwrap();
+        assert_eq!(Number::quadratic_power(sqrt_2), 1);
+        assert_eq!(Number::quadratic_power(sqrt_2.try_sqrt().unwrap()), 2);
+    }
 }
@@ -1,3 +1,14 @@
+use num::rational::Rational64;
+use num::{checked_pow, Integer};
+use std::convert::TryFrom;
+use std::sync::Mutex;
+
+pub fn round_rational(x: Rational64) -> Option<i64> {
+    let numerator = x.numer().checked_mul(2)?.checked_add(*x.denom())?;
+    let denominator = x.denom().checked_mul(2)?;
+    Some(Integer::div_floor(&numerator, &denominator))
+}
+
 pub fn try_sqrt(n: i64) -> Option<i64> {
     if n < 0 {
         return None;
@@ -10,18 +21,254 @@ pub fn try_sqrt(n: i64) -> Option<i64> {
     }
 }
 
-pub fn factorial(n: i64) -> i64 {
+pub fn try_nth_root(x: i64, n: u32) -> Option<i64> {
+    if n == 0 {
+        return None;
+    } else if x < 0 {
+        return if n % 2 == 1 {
+            Some(-try_nth_root(x.checked_neg()?, n)?)
+        } else {
+            None
+        };
+    }
+    let m = (x as f64).powf(1.0 / n as f64).round() as i64;
+    (m - 1..=m + 1).find(|m| *m >= 0 && checked_pow(*m, n as usize) == Some(x))
+}
+
+pub fn exact_log(base: i64, x: Rational64) -> Option<Rational64> {
+    if base < 2 || *x.numer() <= 0 {
+        return None;
+    }
+    let (root, degree) = (2..63)
+        .rev()
+        .find_map(|k| {
+            try_nth_root(base, k)
+                .filter(|r| *r > 1)
+                .map(|r| (r, k as i64))
+        })
+        .unwrap_or((base, 1));
+    let (mut y, sign) = if *x.denom() == 1 {
+        (*x.numer(), 1)
+    } else if *x.numer() == 1 {
+        (*x.denom(), -1)
+    } else {
+        return None;
+    };
+    let mut exponent = 0;
+    while y % root == 0 {
+        y /= root;
+        exponent += 1;
+    }
+    if y == 1 {
+        Some(Rational64::new(sign * exponent, degree))
+    } else {
+        None
+    }
+}
+
+const FACTORIALS: [i64; 21] = {
+    let mut factorials = [1i64; 21];
+    let mut n = 1;
+    while n < factorials.len() {
+        factorials[n] = factorials[n - 1] * n as i64;
+        n += 1;
+    }
+    factorials
+};
+
+pub fn tetration(base: i128, height: i64) -> Option<i128> {
+    if height < 0 {
+        return None;
+    }
+    let mut result = 1i128;
+    for _ in 0..height {
+        result = checked_pow(base, usize::try_from(result).ok()?)?;
+    }
+    Some(result)
+}
+
+pub fn factorial(n: i64) -> Option<i64> {
+    factorial_divide(n, 1)
+}
+
+pub fn subfactorial(n: i64) -> Option<i64> {
     let mut result = 1i64;
-    for x in 2..=n {
-        result *= x;
+    for x in 1..=n {
+        result = result
+            .checked_mul(x)?
+            .checked_add(if x % 2 == 0 { 1 } else { -1 })?;
+    }
+    Some(result)
+}
+
+pub fn double_factorial(n: i64) -> Option<i64> {
+    let mut result = 1i64;
+    for x in (2..=n).rev().step_by(2) {
+        result = result.checked_mul(x)?;
+    }
+    Some(result)
+}
+
+pub fn primes_up_to(limit: i64) -> Vec<i64> {
+    if limit < 2 {
+        return vec![];
+    }
+    let mut is_composite = vec![false; limit as usize + 1];
+    let mut primes = vec![];
+    for x in 2..=limit as usize {
+        if !is_composite[x] {
+            primes.push(x as i64);
+            for y in (x * x..=limit as usize).step_by(x) {
+                is_composite[y] = true;
+            }
+        }
     }
-    result
+    primes
 }
 
-pub fn factorial_divide(m: i64, n: i64) -> i64 {
+pub fn is_prime(n: i64) -> bool {
+    if n < 2 {
+        return false;
+    }
+    for p in [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37] {
+        if n % p == 0 {
+            return n == p;
+        }
+    }
+    let n = n as u64;
+    let mul_mod = |a: u64, b: u64| (a as u128 * b as u128 % n as u128) as u64;
+    let pow_mod = |mut base: u64, mut exponent: u64| {
+        let mut result = 1;
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = mul_mod(result, base);
+            }
+            base = mul_mod(base, base);
+            exponent >>= 1;
+        }
+        result
+    };
+    let s = (n - 1).trailing_zeros();
+    let d = (n - 1) >> s;
+    [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37]
+        .iter()
+        .all(|a| {
+            let mut x = pow_mod(*a, d);
+            if x == 1 || x == n - 1 {
+                return true;
+            }
+            for _ in 1..s {
+                x = mul_mod(x, x);
+                if x == n - 1 {
+                    return true;
+                }
+            }
+            false
+        })
+}
+
+const TRIAL_DIVISION_LIMIT: i128 = 1 << 20;
+
+pub fn prime_factors(n: i128) -> Option<Vec<i128>> {
+    let mut n = n.abs();
+    let mut factors = vec![];
+    let mut p = 2;
+    while p * p <= n {
+        if p > TRIAL_DIVISION_LIMIT {
+            return if i64::try_from(n).is_ok_and(is_prime) {
+                factors.push(n);
+                Some(factors)
+            } else {
+                None
+            };
+        }
+        if n % p == 0 {
+            factors.push(p);
+            while n % p == 0 {
+                n /= p;
+            }
+        }
+        p += 1;
+    }
+    if n > 1 {
+        factors.push(n);
+    }
+    Some(factors)
+}
+
+struct PrimeSieve {
+    limit: i64,
+    primes: Vec<i64>,
+}
+
+static PRIME_SIEVE: Mutex<PrimeSieve> = Mutex::new(PrimeSieve {
+    limit: 1,
+    primes: Vec::new(),
+});
+
+fn with_primes_up_to<R>(limit: i64, f: impl FnOnce(&[i64]) -> R) -> R {
+    let mut sieve = PRIME_SIEVE.lock().unwrap();
+    if sieve.limit < limit {
+        let limit = limit.max(sieve.limit.saturating_mul(2));
+        *sieve = PrimeSieve {
+            limit,
+            primes: primes_up_to(limit),
+        };
+    }
+    f(&sieve.primes)
+}
+
+pub fn nth_prime(k: i64) -> Option<i64> {
+    if k < 1 {
+        return None;
+    }
+    let mut limit = 16;
+    loop {
+        if let Some(p) = with_primes_up_to(limit, |primes| primes.get(k as usize - 1).copied()) {
+            return Some(p);
+        }
+        limit *= 2;
+    }
+}
+
+pub fn prime_pi(k: i64) -> i64 {
+    if k < 2 {
+        return 0;
+    }
+    with_primes_up_to(k, |primes| primes.partition_point(|p| *p <= k) as i64)
+}
+
+pub fn factorial_divide(m: i64, n: i64) -> Option<i64> {
+    if m <= n {
+        return Some(1);
+    } else if n >= 0 && m < FACTORIALS.len() as i64 {
+        return Some(FACTORIALS[m as usize] / FACTORIALS[n as usize]);
+    }
     let mut result = 1i64;
     for x in (n + 1)..=m {
-        result *= x;
+        result = result.checked_mul(x)?;
+    }
+    Some(result)
+}
+
+pub fn number_of_digits(n: i64) -> usize {
+    count_digits(n, 10)
+}
+
+pub fn number_of_base_digits(n: i64, base: u32) -> Option<usize> {
+    if base < 2 {
+        None
+    } else {
+        Some(count_digits(n, base as i64))
+    }
+}
+
+fn count_digits(n: i64, base: i64) -> usize {
+    let mut n = n.abs();
+    let mut digits = 1;
+    while n >= base {
+        n /= base;
+        digits += 1;
     }
-    result
+    digits
 }
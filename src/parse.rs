@@ -0,0 +1,238 @@
+use crate::{Expression, Rc};
+
+const MAX_NESTING_DEPTH: usize = 256;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ParseExpressionError {
+    pub position: usize,
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    position: usize,
+    digits: String,
+    n: i64,
+    depth: usize,
+}
+
+impl Expression {
+    pub fn parse(s: &str, n: i64) -> Result<Rc<Expression>, ParseExpressionError> {
+        let mut parser = Parser {
+            input: s.as_bytes(),
+            position: 0,
+            digits: n.to_string(),
+            n,
+            depth: 0,
+        };
+        let expression = parser.expression()?;
+        parser.skip_whitespace();
+        if parser.position < parser.input.len() {
+            Err(parser.error())
+        } else {
+            Ok(expression)
+        }
+    }
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self) -> ParseExpressionError {
+        ParseExpressionError {
+            position: self.position,
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.position < self.input.len() && self.input[self.position] == b' ' {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_whitespace();
+        self.input.get(self.position).copied()
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        if self.input[self.position..].starts_with(token.as_bytes()) {
+            self.position += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), ParseExpressionError> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.error())
+        }
+    }
+
+    fn nested<R>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<R, ParseExpressionError>,
+    ) -> Result<R, ParseExpressionError> {
+        if self.depth >= MAX_NESTING_DEPTH {
+            return Err(self.error());
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn expression(&mut self) -> Result<Rc<Expression>, ParseExpressionError> {
+        let mut x = if self.eat("-") {
+            Rc::new(Expression::Negate(self.term()?))
+        } else {
+            self.term()?
+        };
+        loop {
+            if self.eat("+") {
+                x = Rc::new(Expression::Add(x, self.term()?));
+            } else if self.eat("-") {
+                x = Rc::new(Expression::Subtract(x, self.term()?));
+            } else {
+                return Ok(x);
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<Rc<Expression>, ParseExpressionError> {
+        let mut x = self.power()?;
+        loop {
+            if self.eat("*") {
+                x = Rc::new(Expression::Multiply(x, self.power()?));
+            } else if self.eat("/") {
+                x = Rc::new(Expression::Divide(x, self.power()?));
+            } else {
+                return Ok(x);
+            }
+        }
+    }
+
+    fn power(&mut self) -> Result<Rc<Expression>, ParseExpressionError> {
+        let x = self.postfix()?;
+        if self.eat("^^") {
+            Ok(Rc::new(Expression::Tetration(x, self.nested(Self::power)?)))
+        } else if self.eat("^") {
+            Ok(Rc::new(Expression::Power(x, self.nested(Self::power)?)))
+        } else {
+            Ok(x)
+        }
+    }
+
+    fn postfix(&mut self) -> Result<Rc<Expression>, ParseExpressionError> {
+        let mut x = self.primary()?;
+        loop {
+            if self.eat("!!") {
+                x = Rc::new(Expression::DoubleFactorial(x));
+            } else if self.eat("!") {
+                x = Rc::new(Expression::Factorial(x));
+            } else if self.eat("%") {
+                x = Rc::new(Expression::Percent(x));
+            } else {
+                return Ok(x);
+            }
+        }
+    }
+
+    fn primary(&mut self) -> Result<Rc<Expression>, ParseExpressionError> {
+        match self.peek() {
+            Some(b'(') => {
+                self.position += 1;
+                let x = self.nested(Self::expression)?;
+                self.expect(")")?;
+                Ok(x)
+            }
+            Some(b'!') => {
+                self.position += 1;
+                Ok(Rc::new(Expression::Subfactorial(
+                    self.nested(Self::primary)?,
+                )))
+            }
+            Some(b'.') => {
+                self.position += 1;
+                let x = self.literal()?;
+                if self.eat("...") {
+                    Ok(Rc::new(Expression::RepeatingDecimal(x)))
+                } else {
+                    Ok(Rc::new(Expression::Decimal(x)))
+                }
+            }
+            Some(c) if c.is_ascii_digit() => Ok(Rc::new(Expression::Number(self.literal()?))),
+            Some(c) if c.is_ascii_alphabetic() => self.nested(Self::function),
+            _ => Err(self.error()),
+        }
+    }
+
+    fn function(&mut self) -> Result<Rc<Expression>, ParseExpressionError> {
+        let start = self.position;
+        while self.position < self.input.len() && self.input[self.position].is_ascii_alphabetic() {
+            self.position += 1;
+        }
+        let name = &self.input[start..self.position];
+        let order = if name == b"sqrt" && self.eat("^") {
+            self.integer()? as usize
+        } else {
+            1
+        };
+        self.expect("(")?;
+        let expression = match name {
+            b"sqrt" => {
+                let x = self.expression()?;
+                match &*x {
+                    Expression::Sqrt(y, z) => Expression::Sqrt(y.clone(), z + order),
+                    _ => Expression::Sqrt(x, order),
+                }
+            }
+            b"cuberoot" => Expression::Root(self.expression()?, 3),
+            b"root" => {
+                let degree = self.integer()? as u32;
+                self.expect(",")?;
+                Expression::Root(self.expression()?, degree)
+            }
+            b"avg" => {
+                let x = self.expression()?;
+                self.expect(",")?;
+                Expression::Average(x, self.expression()?)
+            }
+            b"round" => Expression::Round(self.expression()?),
+            b"prime" => Expression::NthPrime(self.expression()?),
+            b"pi" => Expression::PrimePi(self.expression()?),
+            b"log" => Expression::Log(self.expression()?, self.n),
+            _ => {
+                self.position = start;
+                return Err(self.error());
+            }
+        };
+        self.expect(")")?;
+        Ok(Rc::new(expression))
+    }
+
+    fn integer(&mut self) -> Result<i64, ParseExpressionError> {
+        self.skip_whitespace();
+        let start = self.position;
+        while self.position < self.input.len() && self.input[self.position].is_ascii_digit() {
+            self.position += 1;
+        }
+        std::str::from_utf8(&self.input[start..self.position])
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .ok_or(ParseExpressionError { position: start })
+    }
+
+    fn literal(&mut self) -> Result<i64, ParseExpressionError> {
+        let start = self.position;
+        let x = self.integer()?;
+        let literal = &self.input[start..self.position];
+        let repeats = literal.len() / self.digits.len();
+        if repeats > 0 && literal == self.digits.repeat(repeats).as_bytes() {
+            Ok(x)
+        } else {
+            Err(ParseExpressionError { position: start })
+        }
+    }
+}
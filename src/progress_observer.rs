@@ -0,0 +1,27 @@
+use crate::Expression;
+use std::fmt::Display;
+use std::sync::{Arc, Mutex};
+
+pub trait ProgressObserver: Send {
+    fn on_depth_completed(&mut self, _digits: usize) {}
+
+    fn on_solution_found(&mut self, _expression: &Expression, _digits: usize) {}
+
+    fn on_number_inserted(&mut self, _value: &dyn Display, _digits: usize) {}
+
+    fn on_warning(&mut self, _message: &str) {}
+}
+
+pub(crate) type SharedObserver = Arc<Mutex<Box<dyn ProgressObserver>>>;
+
+pub(crate) struct VerboseObserver;
+
+impl ProgressObserver for VerboseObserver {
+    fn on_depth_completed(&mut self, digits: usize) {
+        eprintln!("depth: {}", digits);
+    }
+
+    fn on_warning(&mut self, message: &str) {
+        eprintln!("warning: {}", message);
+    }
+}
@@ -1,48 +1,295 @@
-use super::{Limits, Solver};
-use crate::{Expression, Number, RationalQuadratic};
+use super::{AllowedOps, Inconsistency, Limits, SearchStats, Solver, SolverState};
+use crate::progress_observer::{SharedObserver, VerboseObserver};
+#[cfg(feature = "parallel")]
+use crate::solver::build_thread_pool;
+use crate::{
+    Domain, Expression, GaussianRational, Number, OpKind, ProgressObserver, RationalQuadratic, Rc,
+};
 use num::rational::Rational64;
-use std::rc::Rc;
+use num::Signed;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::iter;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 enum ProgressiveSearchState {
     None,
     Integral,
     FullIntegral,
     Rational,
     RationalQuadratic,
+    Complex,
     Finished,
 }
 
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct SolverConfig {
+    pub n: i64,
+    pub target: i128,
+    pub rational_target: Option<Rational64>,
+    pub max_depth: Option<usize>,
+    pub integral_limits: Limits,
+    pub rational_limits: Limits,
+    pub quadratic_limits: Limits,
+    pub complex_limits: Option<Limits>,
+    pub integer_only: bool,
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct SolverSnapshot {
+    n: i64,
+    target: i128,
+    rational_target: Option<Rational64>,
+    integral: SolverState<i128>,
+    full_integral: SolverState<i128>,
+    rational: SolverState<Rational64>,
+    quadratic: SolverState<RationalQuadratic>,
+    complex: Option<SolverState<GaussianRational>>,
+    max_depth: Option<usize>,
+    depth_searched: usize,
+    search_state: ProgressiveSearchState,
+}
+
+#[derive(Clone, Debug)]
+pub struct VerifiedSolution {
+    pub expression: Rc<Expression>,
+    pub digits: usize,
+    pub verified: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct PeakStats {
+    pub integral: usize,
+    pub full_integral: usize,
+    pub rational: usize,
+    pub quadratic: usize,
+    pub complex: usize,
+}
+
+#[derive(Clone, Debug)]
+pub enum SearchOutcome {
+    Found(Rc<Expression>, usize),
+    Continue,
+    DepthLimited,
+    Exhausted,
+    TimedOut,
+}
+
+#[derive(Clone, Debug)]
+pub struct SolveReport {
+    pub outcome: SearchOutcome,
+    pub depth_searched: usize,
+    pub peak_stats: PeakStats,
+    pub nearest: Option<(i128, Rc<Expression>, usize)>,
+}
+
 pub struct ProgressiveSolver {
-    target: i64,
+    config: SolverConfig,
+    target: i128,
+    rational_target: Option<Rational64>,
+    negated: bool,
     max_depth: Option<usize>,
-    integral_solver: Solver<i64>,
-    full_integral_solver: Solver<i64>,
+    integral_solver: Solver<i128>,
+    full_integral_solver: Solver<i128>,
     rational_solver: Solver<Rational64>,
     quadratic_solver: Solver<RationalQuadratic>,
+    complex_solver: Option<Solver<GaussianRational>>,
     depth_searched: usize,
     search_state: ProgressiveSearchState,
+    observer: Option<SharedObserver>,
     verbose: bool,
+    cancel_flag: Option<Arc<AtomicBool>>,
 }
 
 impl ProgressiveSolver {
     pub fn new(
         n: i64,
-        target: i64,
+        target: i128,
         max_depth: Option<usize>,
         integral_limits: Limits,
         rational_limits: Limits,
         quadratic_limits: Limits,
+        complex_limits: Option<Limits>,
     ) -> Self {
-        Self {
+        Self::with_config(SolverConfig {
+            n,
             target,
+            rational_target: None,
             max_depth,
-            integral_solver: Solver::<i64>::new_progressive(n, integral_limits),
-            full_integral_solver: Solver::<i64>::new(n, integral_limits),
-            rational_solver: Solver::<Rational64>::new_progressive(n, rational_limits),
-            quadratic_solver: Solver::<RationalQuadratic>::new_progressive(n, quadratic_limits),
+            integral_limits,
+            rational_limits,
+            quadratic_limits,
+            complex_limits,
+            integer_only: false,
+        })
+    }
+
+    pub fn new_rational(
+        n: i64,
+        target: Rational64,
+        max_depth: Option<usize>,
+        integral_limits: Limits,
+        rational_limits: Limits,
+        quadratic_limits: Limits,
+        complex_limits: Option<Limits>,
+    ) -> Self {
+        Self::with_config(SolverConfig {
+            n,
+            target: *target.round().numer() as i128,
+            rational_target: if target.is_integer() {
+                None
+            } else {
+                Some(target)
+            },
+            max_depth,
+            integral_limits,
+            rational_limits,
+            quadratic_limits,
+            complex_limits,
+            integer_only: false,
+        })
+    }
+
+    pub fn integer_only(n: i64, target: i128, max_depth: Option<usize>, limits: Limits) -> Self {
+        Self::with_config(SolverConfig {
+            n,
+            target,
+            rational_target: None,
+            max_depth,
+            integral_limits: limits.clone(),
+            rational_limits: limits.clone(),
+            quadratic_limits: limits,
+            complex_limits: None,
+            integer_only: true,
+        })
+    }
+
+    pub fn solve_restricted(
+        n: i64,
+        target: i128,
+        ops: &[OpKind],
+        allow_concat: bool,
+        max_depth: Option<usize>,
+        limits: Limits,
+    ) -> Option<(Rc<Expression>, usize)> {
+        let mut allowed_ops = if allow_concat {
+            AllowedOps::CONCAT
+        } else {
+            AllowedOps::empty()
+        };
+        for op in ops {
+            allowed_ops |= AllowedOps::from(*op);
+        }
+        let limits = Limits {
+            allowed_ops,
+            ..limits
+        };
+        Self::new(
+            n,
+            target,
+            max_depth,
+            limits.clone(),
+            limits.clone(),
+            limits,
+            None,
+        )
+        .solve()
+        .last()
+    }
+
+    pub fn with_config(config: SolverConfig) -> Self {
+        let n = config.n;
+        let negated = config.integral_limits.free_negation
+            && config
+                .rational_target
+                .map_or(config.target < 0, |target| target.is_negative());
+        Self {
+            target: if negated {
+                -config.target
+            } else {
+                config.target
+            },
+            rational_target: config
+                .rational_target
+                .map(|target| if negated { -target } else { target }),
+            negated,
+            max_depth: config.max_depth,
+            integral_solver: Solver::<i128>::new_progressive(n, config.integral_limits.clone()),
+            full_integral_solver: Solver::<i128>::new(n, config.integral_limits.clone()),
+            rational_solver: Solver::<Rational64>::new_progressive(
+                n,
+                config.rational_limits.clone(),
+            ),
+            quadratic_solver: Solver::<RationalQuadratic>::new_progressive(
+                n,
+                config.quadratic_limits.clone(),
+            ),
+            complex_solver: config
+                .complex_limits
+                .clone()
+                .map(|limits| Solver::<GaussianRational>::new_progressive(n, limits)),
             depth_searched: 0,
             search_state: ProgressiveSearchState::None,
+            observer: None,
             verbose: false,
+            cancel_flag: None,
+            config,
+        }
+    }
+
+    pub fn restore(snapshot: SolverSnapshot, config: SolverConfig) -> Option<Self> {
+        if (snapshot.n, snapshot.target, snapshot.rational_target)
+            != (config.n, config.target, config.rational_target)
+        {
+            return None;
+        }
+        let mut solver = Self::with_config(config);
+        solver.integral_solver.restore_state(snapshot.integral);
+        solver
+            .full_integral_solver
+            .restore_state(snapshot.full_integral);
+        solver.rational_solver.restore_state(snapshot.rational);
+        solver.quadratic_solver.restore_state(snapshot.quadratic);
+        if let (Some(complex_solver), Some(complex)) =
+            (&mut solver.complex_solver, snapshot.complex)
+        {
+            complex_solver.restore_state(complex);
+        }
+        solver.max_depth = snapshot.max_depth;
+        solver.depth_searched = snapshot.depth_searched;
+        solver.search_state = snapshot.search_state;
+        Some(solver)
+    }
+
+    #[inline]
+    pub fn config(&self) -> SolverConfig {
+        self.config.clone()
+    }
+
+    pub fn save_state(&self) -> SolverSnapshot {
+        SolverSnapshot {
+            n: self.config.n,
+            target: self.config.target,
+            rational_target: self.config.rational_target,
+            integral: self.integral_solver.save_state(),
+            full_integral: self.full_integral_solver.save_state(),
+            rational: self.rational_solver.save_state(),
+            quadratic: self.quadratic_solver.save_state(),
+            complex: self
+                .complex_solver
+                .as_ref()
+                .map(|complex_solver| complex_solver.save_state()),
+            max_depth: self.max_depth,
+            depth_searched: self.depth_searched,
+            search_state: self.search_state.clone(),
         }
     }
 
@@ -50,125 +297,559 @@ impl ProgressiveSolver {
         self.verbose = verbose;
     }
 
-    pub fn solve(&mut self) -> SolverIterator {
+    pub fn set_observer(&mut self, observer: Box<dyn ProgressObserver>) {
+        self.install_observer(Some(Arc::new(Mutex::new(observer))));
+    }
+
+    pub fn clear_observer(&mut self) {
+        self.install_observer(None);
+    }
+
+    fn install_observer(&mut self, observer: Option<SharedObserver>) {
+        self.integral_solver.set_observer(observer.clone());
+        self.full_integral_solver.set_observer(observer.clone());
+        self.rational_solver.set_observer(observer.clone());
+        self.quadratic_solver.set_observer(observer.clone());
+        if let Some(complex_solver) = &mut self.complex_solver {
+            complex_solver.set_observer(observer.clone());
+        }
+        self.observer = observer;
+    }
+
+    fn notify(&self, mut event: impl FnMut(&mut dyn ProgressObserver)) {
+        if self.verbose {
+            event(&mut VerboseObserver);
+        }
+        if let Some(observer) = &self.observer {
+            event(observer.lock().unwrap().as_mut());
+        }
+    }
+
+    pub fn set_cancel_flag(&mut self, cancel_flag: Arc<AtomicBool>) {
+        self.integral_solver.set_cancel_flag(cancel_flag.clone());
+        self.full_integral_solver
+            .set_cancel_flag(cancel_flag.clone());
+        self.rational_solver.set_cancel_flag(cancel_flag.clone());
+        self.quadratic_solver.set_cancel_flag(cancel_flag.clone());
+        if let Some(complex_solver) = &mut self.complex_solver {
+            complex_solver.set_cancel_flag(cancel_flag.clone());
+        }
+        self.cancel_flag = Some(cancel_flag);
+    }
+
+    #[cfg(feature = "parallel")]
+    pub fn set_threads(&mut self, threads: usize) {
+        let thread_pool = build_thread_pool(threads);
+        self.integral_solver.set_thread_pool(thread_pool.clone());
+        self.full_integral_solver
+            .set_thread_pool(thread_pool.clone());
+        self.rational_solver.set_thread_pool(thread_pool.clone());
+        self.quadratic_solver.set_thread_pool(thread_pool.clone());
+        if let Some(complex_solver) = &mut self.complex_solver {
+            complex_solver.set_thread_pool(thread_pool);
+        }
+    }
+
+    pub fn solve(&mut self) -> SolverIterator<'_> {
         SolverIterator { solver: self }
     }
 
-    pub fn get_solution(&self, x: &i64) -> Option<&(Rc<Expression>, usize)> {
+    pub fn solve_step(&mut self) -> SearchOutcome {
+        if self.is_exhausted() {
+            return SearchOutcome::Exhausted;
+        }
+        if self.depth_searched >= self.max_depth.unwrap_or(usize::MAX) {
+            return SearchOutcome::DepthLimited;
+        }
+        if self.search(self.depth_searched + 1) {
+            if let Some((expression, digits)) = self.take_solution() {
+                return SearchOutcome::Found(expression, digits);
+            }
+        }
+        SearchOutcome::Continue
+    }
+
+    pub fn solve_iter(&mut self) -> impl Iterator<Item = (Rc<Expression>, usize)> + '_ {
+        iter::from_fn(move || loop {
+            match self.solve_step() {
+                SearchOutcome::Found(expression, digits) => return Some((expression, digits)),
+                SearchOutcome::Continue if !self.is_cancelled() => {}
+                _ => return None,
+            }
+        })
+    }
+
+    pub fn solve_with_improvements(
+        &mut self,
+        mut on_improvement: impl FnMut(&Rc<Expression>, usize),
+    ) -> Option<(Rc<Expression>, usize)> {
+        let mut best = None;
+        for (expression, digits) in self.solve_iter() {
+            on_improvement(&expression, digits);
+            best = Some((expression, digits));
+        }
+        best
+    }
+
+    pub fn solve_report(&mut self, time_limit: Option<Duration>) -> SolveReport {
+        let start = Instant::now();
+        let mut solution = None;
+        let mut timed_out = false;
+        while self.depth_searched < self.max_depth.unwrap_or(usize::MAX) && !self.is_exhausted() {
+            if self.is_cancelled() || time_limit.is_some_and(|limit| start.elapsed() >= limit) {
+                timed_out = true;
+                break;
+            }
+            if self.search(self.depth_searched + 1) {
+                solution = self.take_solution();
+            }
+        }
+        let (outcome, nearest) = match solution {
+            Some((expression, digits)) => (SearchOutcome::Found(expression, digits), None),
+            None => {
+                let outcome = if timed_out {
+                    SearchOutcome::TimedOut
+                } else if self.is_exhausted() {
+                    SearchOutcome::Exhausted
+                } else {
+                    SearchOutcome::DepthLimited
+                };
+                (outcome, self.nearest_int())
+            }
+        };
+        SolveReport {
+            outcome,
+            depth_searched: self.depth_searched,
+            peak_stats: self.peak_stats(),
+            nearest,
+        }
+    }
+
+    pub fn solve_outcome(&mut self) -> SearchOutcome {
+        self.solve_report(None).outcome
+    }
+
+    pub fn solve_all(&mut self) -> Vec<Rc<Expression>> {
+        let digits = match self.solve().last() {
+            Some((_, digits)) => digits,
+            None => return vec![],
+        };
+        let integral_target = self.integral_target();
+        let rational_target = self.narrow_target::<Rational64>();
+        let quadratic_target = self.narrow_target::<RationalQuadratic>();
+        let complex_target = self.narrow_target::<GaussianRational>();
+        let mut solutions: Vec<Rc<Expression>> = vec![];
+        let stored = [
+            integral_target.and_then(|target| self.integral_solver.get_solution(&target)),
+            integral_target.and_then(|target| self.full_integral_solver.get_solution(&target)),
+            rational_target.and_then(|target| self.rational_solver.get_solution(&target)),
+            quadratic_target.and_then(|target| self.quadratic_solver.get_solution(&target)),
+            self.complex_solver
+                .as_ref()
+                .zip(complex_target)
+                .and_then(|(complex_solver, target)| complex_solver.get_solution(&target)),
+        ];
+        let mut candidates: Vec<Rc<Expression>> = stored
+            .iter()
+            .flatten()
+            .filter(|(_, x_digits)| *x_digits == digits)
+            .map(|(expression, _)| expression.clone())
+            .collect();
+        if let Some(target) = integral_target {
+            candidates.extend(self.integral_solver.solutions_at(target, digits));
+            candidates.extend(self.full_integral_solver.solutions_at(target, digits));
+        }
+        if let Some(target) = rational_target {
+            candidates.extend(self.rational_solver.solutions_at(target, digits));
+        }
+        if let Some(target) = quadratic_target {
+            candidates.extend(self.quadratic_solver.solutions_at(target, digits));
+        }
+        if let (Some(complex_solver), Some(target)) = (&mut self.complex_solver, complex_target) {
+            candidates.extend(complex_solver.solutions_at(target, digits));
+        }
+        let mut seen: HashMap<u64, Vec<Rc<Expression>>> = HashMap::new();
+        for expression in candidates {
+            let bucket = seen.entry(expression.canonical_hash()).or_default();
+            if bucket.iter().all(|x| !x.canonically_eq(&expression)) {
+                bucket.push(expression.clone());
+                solutions.push(if self.negated {
+                    Expression::from_negate(expression)
+                } else {
+                    expression
+                });
+            }
+        }
+        solutions
+    }
+
+    pub fn solve_verified(&mut self) -> Option<VerifiedSolution> {
+        let (expression, digits) = self.solve().last()?;
+        let target = match self.config.rational_target {
+            Some(target) => target.to_string(),
+            None => self.config.target.to_string(),
+        };
+        let evaluated = expression.evaluate();
+        let verified = evaluated.is_some()
+            && evaluated
+                == match self.config.rational_target {
+                    Some(target) => Some(target.into()),
+                    None => RationalQuadratic::try_from(self.config.target).ok(),
+                };
+        debug_assert!(
+            evaluated.is_none() || verified,
+            "{} does not evaluate to {}",
+            expression,
+            target
+        );
+        if !verified {
+            self.notify(|observer| {
+                observer.on_warning(&format!("could not verify {} = {}", expression, target))
+            });
+        }
+        Some(VerifiedSolution {
+            expression,
+            digits,
+            verified,
+        })
+    }
+
+    pub fn solve_with_domain(&mut self) -> Option<(Rc<Expression>, usize, Domain)> {
+        let (expression, digits) = self.solve().last()?;
+        let domain = expression.domain();
+        Some((expression, digits, domain))
+    }
+
+    pub fn get_solution(&self, x: &i128) -> Option<&(Rc<Expression>, usize)> {
+        let narrow_x = i64::try_from(*x).ok();
         self.integral_solver
             .get_solution(x)
-            .or_else(|| self.rational_solver.get_solution(&(*x).into()))
-            .or_else(|| self.quadratic_solver.get_solution(&(*x).into()))
+            .or_else(|| self.rational_solver.get_solution(&narrow_x?.into()))
+            .or_else(|| self.quadratic_solver.get_solution(&narrow_x?.into()))
             .or_else(|| self.full_integral_solver.get_solution(x))
+            .or_else(|| {
+                self.complex_solver
+                    .as_ref()?
+                    .get_solution(&narrow_x?.into())
+            })
+    }
+
+    pub fn get_rational_solution(&self, x: &Rational64) -> Option<&(Rc<Expression>, usize)> {
+        if x.is_integer() {
+            return self.get_solution(&(*x.numer()).into());
+        }
+        self.rational_solver
+            .get_solution(x)
+            .or_else(|| self.quadratic_solver.get_solution(&(*x).into()))
+            .or_else(|| self.complex_solver.as_ref()?.get_solution(&(*x).into()))
+    }
+
+    pub fn peak_stats(&self) -> PeakStats {
+        PeakStats {
+            integral: self.integral_solver.peak_size(),
+            full_integral: self.full_integral_solver.peak_size(),
+            rational: self.rational_solver.peak_size(),
+            quadratic: self.quadratic_solver.peak_size(),
+            complex: self
+                .complex_solver
+                .as_ref()
+                .map_or(0, |complex_solver| complex_solver.peak_size()),
+        }
+    }
+
+    pub fn stats(&self) -> SearchStats {
+        let mut stats = SearchStats::default();
+        let tiers = [
+            Some(self.integral_solver.stats()),
+            Some(self.full_integral_solver.stats()),
+            Some(self.rational_solver.stats()),
+            Some(self.quadratic_solver.stats()),
+            self.complex_solver
+                .as_ref()
+                .map(|complex_solver| complex_solver.stats()),
+        ];
+        for tier in tiers.iter().flatten() {
+            stats.candidates += tier.candidates;
+            stats.inserted += tier.inserted;
+            stats.out_of_range += tier.out_of_range;
+            stats.already_searched += tier.already_searched;
+            stats.skipped_follow_ups += tier.skipped_follow_ups;
+            stats.table_size += tier.table_size;
+            #[cfg(feature = "search-timing")]
+            {
+                if stats.depth_times.len() < tier.depth_times.len() {
+                    stats
+                        .depth_times
+                        .resize(tier.depth_times.len(), Default::default());
+                }
+                for (total, time) in stats.depth_times.iter_mut().zip(tier.depth_times.iter()) {
+                    *total += *time;
+                }
+            }
+        }
+        stats
+    }
+
+    pub fn validate(&self) -> Result<(), Vec<Inconsistency>> {
+        let mut inconsistencies = self.integral_solver.validate();
+        inconsistencies.append(&mut self.full_integral_solver.validate());
+        inconsistencies.append(&mut self.rational_solver.validate());
+        inconsistencies.append(&mut self.quadratic_solver.validate());
+        if inconsistencies.is_empty() {
+            Ok(())
+        } else {
+            Err(inconsistencies)
+        }
     }
 
     pub(crate) fn solve_next(&mut self) -> Option<(Rc<Expression>, usize)> {
         for digits in self.depth_searched + 1..=self.max_depth.unwrap_or(usize::MAX) {
             if self.search(digits) {
-                let solution = self.get_solution(&self.target)?.clone();
-                self.max_depth = Some(solution.1 - 1);
-                return Some(solution);
+                return self.take_solution();
+            }
+            if self.is_cancelled() {
+                return None;
             }
         }
         None
     }
 
+    fn is_cancelled(&self) -> bool {
+        self.cancel_flag
+            .as_ref()
+            .is_some_and(|flag| flag.load(Ordering::Relaxed))
+    }
+
+    pub(crate) fn solution(&self) -> Option<(Rc<Expression>, usize)> {
+        let (expression, digits) = match self.rational_target {
+            Some(target) => self.get_rational_solution(&target)?,
+            None => self.get_solution(&self.target)?,
+        }
+        .clone();
+        if self.negated {
+            Some((Expression::from_negate(expression), digits))
+        } else {
+            Some((expression, digits))
+        }
+    }
+
+    fn take_solution(&mut self) -> Option<(Rc<Expression>, usize)> {
+        let (expression, digits) = self.solution()?;
+        self.max_depth = Some(digits - 1);
+        debug_assert!(
+            expression.evaluate().is_none()
+                || match self.config.rational_target {
+                    Some(target) => expression.verify(self.config.n, target),
+                    None => expression.verify(self.config.n, self.config.target),
+                },
+            "{} does not evaluate to the target",
+            expression
+        );
+        self.notify(|observer| observer.on_solution_found(&expression, digits));
+        Some((expression, digits))
+    }
+
+    fn insert_trivial_solution(&mut self) -> bool {
+        let n = self.config.n;
+        if self.rational_target.is_some() || self.target != n as i128 {
+            return false;
+        }
+        self.integral_solver
+            .try_insert(n.into(), 1, || Expression::from_number(n));
+        self.integral_solver.get_solution(&self.target).is_some()
+    }
+
+    fn is_exhausted(&self) -> bool {
+        self.integral_solver.is_exhausted()
+            && (self.config.integer_only
+                || self.rational_solver.is_exhausted()
+                    && self.quadratic_solver.is_exhausted()
+                    && self
+                        .complex_solver
+                        .as_ref()
+                        .is_none_or(|complex_solver| complex_solver.is_exhausted()))
+    }
+
+    fn integral_target(&self) -> Option<i128> {
+        if self.rational_target.is_some() {
+            None
+        } else {
+            Some(self.target)
+        }
+    }
+
+    fn narrow_target<T: From<Rational64>>(&self) -> Option<T> {
+        self.rational_target
+            .or_else(|| Some(i64::try_from(self.target).ok()?.into()))
+            .map(T::from)
+    }
+
+    fn nearest_int(&self) -> Option<(i128, Rc<Expression>, usize)> {
+        let (x, expression, digits) = [
+            self.integral_solver.nearest_int(self.target),
+            self.rational_solver.nearest_int(self.target),
+            self.quadratic_solver.nearest_int(self.target),
+            self.complex_solver
+                .as_ref()
+                .and_then(|complex_solver| complex_solver.nearest_int(self.target)),
+        ]
+        .iter()
+        .flatten()
+        .min_by_key(|(x, _, digits)| ((x - self.target).abs(), *digits))?
+        .clone();
+        Some(if self.negated {
+            (-x, Expression::from_negate(expression), digits)
+        } else {
+            (x, expression, digits)
+        })
+    }
+
     fn search(&mut self, digits: usize) -> bool {
-        match self.search_state {
-            ProgressiveSearchState::None => {
-                self.search_state = ProgressiveSearchState::Integral;
-            }
-            _ => {}
-        }
-        match self.search_state {
-            ProgressiveSearchState::Integral => {
-                if self
-                    .integral_solver
-                    .solve(self.target, Some(digits))
-                    .is_some()
-                {
+        if digits == 1 && self.insert_trivial_solution() {
+            return true;
+        }
+        if let ProgressiveSearchState::None = self.search_state {
+            self.search_state = ProgressiveSearchState::Integral;
+        }
+        if let ProgressiveSearchState::Integral = self.search_state {
+            if let Some(target) = self.integral_target() {
+                if self.integral_solver.solve(target, Some(digits)).is_some() {
                     return true;
                 }
+            } else {
+                self.integral_solver.grow_to_depth(digits);
+            }
+            if self.is_cancelled() {
+                return false;
+            }
+            if !self.config.integer_only {
                 for (x, expression, _) in self.integral_solver.new_numbers() {
+                    let x = match i64::try_from(*x) {
+                        Ok(x) => x,
+                        Err(_) => continue,
+                    };
                     self.rational_solver
-                        .try_insert((*x).into(), digits, || expression.clone());
+                        .try_insert(x.into(), digits, || expression.clone());
                     self.quadratic_solver
-                        .try_insert((*x).into(), digits, || expression.clone());
+                        .try_insert(x.into(), digits, || expression.clone());
+                    if let Some(complex_solver) = &mut self.complex_solver {
+                        complex_solver.try_insert(x.into(), digits, || expression.clone());
+                    }
                 }
-                self.clear_new_numbers();
-                self.search_state = ProgressiveSearchState::FullIntegral;
             }
-            _ => {}
+            self.clear_new_numbers();
+            self.search_state = ProgressiveSearchState::FullIntegral;
         }
-        match self.search_state {
-            ProgressiveSearchState::FullIntegral => {
-                let mut found = false;
+        if let ProgressiveSearchState::FullIntegral = self.search_state {
+            let mut found = false;
+            if let Some(target) = self.integral_target() {
                 if digits >= 3 && digits < self.max_depth.unwrap_or(usize::MAX) {
                     self.full_integral_solver
                         .clone_non_pregressive_from(&self.integral_solver);
                     found = self
                         .full_integral_solver
-                        .solve(self.target, self.max_depth)
+                        .solve(target, self.max_depth)
                         .is_some();
                 }
-                self.search_state = ProgressiveSearchState::Rational;
-                if found {
+            }
+            if !found && self.is_cancelled() {
+                return false;
+            }
+            self.search_state = if self.config.integer_only {
+                ProgressiveSearchState::Finished
+            } else {
+                ProgressiveSearchState::Rational
+            };
+            if found {
+                return true;
+            }
+        }
+        if let ProgressiveSearchState::Rational = self.search_state {
+            if let Some(target) = self.narrow_target() {
+                if self.rational_solver.solve(target, Some(digits)).is_some() {
                     return true;
                 }
+            } else {
+                self.rational_solver.grow_to_depth(digits);
             }
-            _ => {}
+            if self.is_cancelled() {
+                return false;
+            }
+            for (x, expression, _) in self.rational_solver.new_numbers() {
+                if let Some(x_int) = x.to_int() {
+                    self.integral_solver
+                        .try_insert(x_int.into(), digits, || expression.clone());
+                }
+                self.quadratic_solver
+                    .try_insert((*x).into(), digits, || expression.clone());
+                if let Some(complex_solver) = &mut self.complex_solver {
+                    complex_solver.try_insert((*x).into(), digits, || expression.clone());
+                }
+            }
+            self.clear_new_numbers();
+            self.search_state = ProgressiveSearchState::RationalQuadratic;
         }
-        match self.search_state {
-            ProgressiveSearchState::Rational => {
-                if self
-                    .rational_solver
-                    .solve(self.target.into(), Some(digits))
-                    .is_some()
-                {
+        if let ProgressiveSearchState::RationalQuadratic = self.search_state {
+            if let Some(target) = self.narrow_target() {
+                if self.quadratic_solver.solve(target, Some(digits)).is_some() {
                     return true;
                 }
-                for (x, expression, _) in self.rational_solver.new_numbers() {
-                    if let Some(x_int) = x.to_int() {
-                        self.integral_solver
-                            .try_insert(x_int, digits, || expression.clone());
+            } else {
+                self.quadratic_solver.grow_to_depth(digits);
+            }
+            if self.is_cancelled() {
+                return false;
+            }
+            for (x, expression, _) in self.quadratic_solver.new_numbers() {
+                if let Some(x_int) = x.to_int() {
+                    self.integral_solver
+                        .try_insert(x_int.into(), digits, || expression.clone());
+                }
+                if x.is_rational() {
+                    self.rational_solver
+                        .try_insert(x.rational_part(), digits, || expression.clone());
+                    if let Some(complex_solver) = &mut self.complex_solver {
+                        complex_solver
+                            .try_insert(x.rational_part().into(), digits, || expression.clone());
                     }
-                    self.quadratic_solver
-                        .try_insert((*x).into(), digits, || expression.clone());
                 }
-                self.clear_new_numbers();
-                self.search_state = ProgressiveSearchState::RationalQuadratic;
-            }
-            _ => {}
-        }
-        match self.search_state {
-            ProgressiveSearchState::RationalQuadratic => {
-                if self
-                    .quadratic_solver
-                    .solve(self.target.into(), Some(digits))
-                    .is_some()
-                {
-                    return true;
+            }
+            self.clear_new_numbers();
+            self.search_state = ProgressiveSearchState::Complex;
+        }
+        if let ProgressiveSearchState::Complex = self.search_state {
+            let target = self.narrow_target();
+            if let Some(complex_solver) = &mut self.complex_solver {
+                if let Some(target) = target {
+                    if complex_solver.solve(target, Some(digits)).is_some() {
+                        return true;
+                    }
+                } else {
+                    complex_solver.grow_to_depth(digits);
+                }
+                if complex_solver.is_cancelled() {
+                    return false;
                 }
-                for (x, expression, _) in self.quadratic_solver.new_numbers() {
+                for (x, expression, _) in complex_solver.new_numbers() {
+                    if !x.is_real() || !x.is_positive() {
+                        continue;
+                    }
                     if let Some(x_int) = x.to_int() {
                         self.integral_solver
-                            .try_insert(x_int, digits, || expression.clone());
-                    }
-                    if x.is_rational() {
-                        self.rational_solver
-                            .try_insert(x.rational_part(), digits, || expression.clone());
+                            .try_insert(x_int.into(), digits, || expression.clone());
                     }
+                    self.rational_solver
+                        .try_insert(x.real_part(), digits, || expression.clone());
+                    self.quadratic_solver
+                        .try_insert(x.real_part().into(), digits, || expression.clone());
                 }
-                self.clear_new_numbers();
-                self.search_state = ProgressiveSearchState::Finished;
             }
-            _ => {}
+            self.clear_new_numbers();
+            self.search_state = ProgressiveSearchState::Finished;
         }
         self.depth_searched = digits;
         self.search_state = ProgressiveSearchState::None;
-        if self.verbose {
-            eprintln!("depth: {}", digits);
-        }
+        self.notify(|observer| observer.on_depth_completed(digits));
         false
     }
 
@@ -176,6 +857,9 @@ impl ProgressiveSolver {
         self.integral_solver.clear_new_numbers();
         self.rational_solver.clear_new_numbers();
         self.quadratic_solver.clear_new_numbers();
+        if let Some(complex_solver) = &mut self.complex_solver {
+            complex_solver.clear_new_numbers();
+        }
     }
 }
 
@@ -1,8 +1,11 @@
-use super::{IntegralQuadratic, ParseQuadraticError, PRIMES};
-use crate::number_theory::try_sqrt;
+use super::{
+    round_surd, surd_latex_string, surd_string, IntegralQuadratic, ParseQuadraticError,
+    RationalQuadratic, MAX_QUADRATIC_POWER, PRIMES,
+};
+use crate::number_theory::{try_nth_root, try_sqrt};
 use crate::Number;
 use num::traits::Pow;
-use num::{Integer, Num, One, Signed, Zero};
+use num::{checked_pow, Integer, Num, One, Signed, Zero};
 use opimps;
 use std::fmt;
 use std::ops::{Add, Div, Mul, Neg, Rem, Sub};
@@ -22,6 +25,42 @@ impl IntegralQuadratic {
     pub fn quadratic_power(&self) -> u8 {
         self.quadratic_power
     }
+
+    pub fn to_f64(&self) -> f64 {
+        let mut result = self.integral_part as f64;
+        for (prime, power) in PRIMES.iter().zip(self.quadratic_part.iter()) {
+            if *power > 0 {
+                result *=
+                    (*prime as f64).powf(*power as f64 / 2f64.powi(self.quadratic_power as i32));
+            }
+        }
+        result
+    }
+
+    pub fn round(&self) -> Option<i64> {
+        if self.quadratic_power == 0 {
+            Some(self.integral_part)
+        } else {
+            round_surd(
+                self.integral_part,
+                1,
+                &self.quadratic_part,
+                self.quadratic_power,
+            )
+        }
+    }
+
+    pub fn to_latex_string(&self) -> String {
+        if self.quadratic_power == 0 {
+            return self.integral_part.to_string();
+        }
+        let quadratic_string = surd_latex_string(&self.quadratic_part, self.quadratic_power);
+        match self.integral_part {
+            1 => quadratic_string,
+            -1 => "-".to_string() + &quadratic_string,
+            _ => self.integral_part.to_string() + &quadratic_string,
+        }
+    }
 }
 
 impl fmt::Display for IntegralQuadratic {
@@ -29,16 +68,7 @@ impl fmt::Display for IntegralQuadratic {
         if self.quadratic_power == 0 {
             write!(f, "{}", self.integral_part)
         } else {
-            let mut number_under_sqrt = 1;
-            for i in 0..PRIMES.len() {
-                number_under_sqrt *= PRIMES[i].pow(self.quadratic_part[i] as u32);
-            }
-            let quadratic_string = format!(
-                "{}{}{}",
-                "sqrt(".repeat(self.quadratic_power as usize),
-                number_under_sqrt,
-                ")".repeat(self.quadratic_power as usize)
-            );
+            let quadratic_string = surd_string(&self.quadratic_part, self.quadratic_power);
             if self.integral_part == 1 {
                 write!(f, "{}", quadratic_string)
             } else if self.integral_part == -1 {
@@ -80,13 +110,51 @@ impl Number for IntegralQuadratic {
     fn is_rational(self) -> bool {
         self.quadratic_power == 0
     }
+
+    #[inline]
+    fn try_sqrt(self) -> Option<Self> {
+        IntegralQuadratic::try_sqrt(&self)
+    }
+
+    fn try_nth_root(self, n: u32) -> Option<Self> {
+        if n == 0 {
+            None
+        } else if n == 1 {
+            Some(self)
+        } else if n.is_multiple_of(2) {
+            self.try_sqrt()?.try_nth_root(n / 2)
+        } else if self.quadratic_power == 0 {
+            Some(try_nth_root(self.integral_part, n)?.into())
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn to_f64(self) -> f64 {
+        IntegralQuadratic::to_f64(&self)
+    }
+
+    #[inline]
+    fn quadratic_power(self) -> u8 {
+        self.quadratic_power
+    }
 }
 
 impl Num for IntegralQuadratic {
     type FromStrRadixErr = ParseQuadraticError;
 
-    fn from_str_radix(_str: &str, _radix: u32) -> Result<Self, Self::FromStrRadixErr> {
-        Err(ParseQuadraticError {})
+    fn from_str_radix(str: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+        let x = RationalQuadratic::from_str_radix(str, radix)?;
+        if x.rational_part.is_integer() {
+            Ok(Self {
+                integral_part: *x.rational_part.numer(),
+                quadratic_part: x.quadratic_part,
+                quadratic_power: x.quadratic_power,
+            })
+        } else {
+            Err(ParseQuadraticError::MalformedFraction)
+        }
     }
 }
 
@@ -227,6 +295,7 @@ fn mul(self: IntegralQuadratic, rhs: IntegralQuadratic) -> IntegralQuadratic {
     }
     let mut quadratic_part = [0u8; PRIMES.len()];
     let mut quadratic_power = u8::max(self.quadratic_power, rhs.quadratic_power);
+    debug_assert!(quadratic_power <= MAX_QUADRATIC_POWER);
     if quadratic_power > 0 {
         for i in 0..PRIMES.len() {
             quadratic_part[i] = (self.quadratic_part[i]
@@ -275,6 +344,7 @@ fn div(self: IntegralQuadratic, rhs: IntegralQuadratic) -> IntegralQuadratic {
     }
     let mut quadratic_part = [0u8; PRIMES.len()];
     let mut quadratic_power = u8::max(self.quadratic_power, rhs.quadratic_power);
+    debug_assert!(quadratic_power <= MAX_QUADRATIC_POWER);
     if quadratic_power > 0 {
         for i in 0..PRIMES.len() {
             let x = self.quadratic_part[i] << (quadratic_power - self.quadratic_power);
@@ -327,7 +397,7 @@ impl Pow<u32> for IntegralQuadratic {
         let mut quadratic_part = [0u8; PRIMES.len()];
         let mut quadratic_power = self.quadratic_power;
         let mut power = power;
-        while quadratic_power > 0 && power % 2 == 0 {
+        while quadratic_power > 0 && power.is_multiple_of(2) {
             quadratic_power -= 1;
             power >>= 1;
         }
@@ -346,6 +416,32 @@ impl Pow<u32> for IntegralQuadratic {
 }
 
 impl IntegralQuadratic {
+    pub fn checked_pow(&self, power: u32) -> Option<Self> {
+        if power == 0 {
+            return Some(Self::one());
+        }
+        let mut integral_part = checked_pow(self.integral_part, power as usize)?;
+        let mut quadratic_part = [0u8; PRIMES.len()];
+        let mut quadratic_power = self.quadratic_power;
+        let mut power = power;
+        while quadratic_power > 0 && power.is_multiple_of(2) {
+            quadratic_power -= 1;
+            power >>= 1;
+        }
+        for i in 0..PRIMES.len() {
+            let prime_power = ((self.quadratic_part[i] as u32).checked_mul(power)?)
+                .div_mod_floor(&(1 << quadratic_power));
+            integral_part =
+                integral_part.checked_mul(checked_pow(PRIMES[i], prime_power.0 as usize)?)?;
+            quadratic_part[i] = prime_power.1 as u8;
+        }
+        Some(Self {
+            integral_part,
+            quadratic_part,
+            quadratic_power,
+        })
+    }
+
     pub fn is_divisible_by(&self, rhs: &Self) -> bool {
         if self.integral_part % rhs.integral_part != 0 {
             return false;
@@ -387,6 +483,8 @@ impl IntegralQuadratic {
         integral_part *= try_sqrt(p)?;
         if quadratic_part.iter().all(|x| *x == 0) {
             quadratic_power = 0;
+        } else if quadratic_power > MAX_QUADRATIC_POWER {
+            return None;
         }
         Some(Self {
             integral_part,
@@ -1,22 +1,219 @@
 use num::rational::Rational64;
+#[cfg(feature = "serde")]
+use num::Integer;
+use num::{BigUint, One};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
 
 mod integral;
 mod rational;
 
-pub const PRIMES: [i64; 4] = [2, 3, 5, 7];
+const ALL_PRIMES: [i64; 32] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131,
+];
 
-#[derive(Clone, Copy, Eq, Hash, PartialEq)]
+// Each `primes-*` feature enables the smaller ones, so the largest enabled table is used.
+const PRIME_COUNT: usize = if cfg!(feature = "primes-32") {
+    32
+} else if cfg!(feature = "primes-16") {
+    16
+} else if cfg!(feature = "primes-8") {
+    8
+} else {
+    4
+};
+
+pub const PRIMES: [i64; PRIME_COUNT] = {
+    let mut primes = [0; PRIME_COUNT];
+    let mut i = 0;
+    while i < PRIME_COUNT {
+        primes[i] = ALL_PRIMES[i];
+        i += 1;
+    }
+    primes
+};
+
+// Each slot of `quadratic_part` stores an exponent below `1 << quadratic_power` in a `u8`.
+pub const MAX_QUADRATIC_POWER: u8 = 7;
+
+fn number_under_sqrt(quadratic_part: &[u8; PRIMES.len()]) -> BigUint {
+    PRIMES
+        .iter()
+        .zip(quadratic_part.iter())
+        .map(|(prime, power)| BigUint::from(*prime as u64).pow(*power as u32))
+        .product()
+}
+
+// Level `i` (outermost first) takes bit `quadratic_power - i` of each exponent, so the surd is
+// `sqrt(F1*sqrt(F2*...sqrt(Fk)))`.
+fn surd_levels(quadratic_part: &[u8; PRIMES.len()], quadratic_power: u8) -> Vec<BigUint> {
+    (1..=quadratic_power)
+        .map(|level| {
+            let bit = quadratic_power - level;
+            PRIMES
+                .iter()
+                .zip(quadratic_part.iter())
+                .filter(|(_, power)| *power >> bit & 1 == 1)
+                .map(|(prime, _)| BigUint::from(*prime as u64))
+                .product()
+        })
+        .collect()
+}
+
+fn format_surd(
+    quadratic_part: &[u8; PRIMES.len()],
+    quadratic_power: u8,
+    format_level: impl Fn(&BigUint, Option<String>) -> String,
+) -> String {
+    surd_levels(quadratic_part, quadratic_power)
+        .iter()
+        .rev()
+        .fold(None, |inner, radicand| Some(format_level(radicand, inner)))
+        .unwrap_or_default()
+}
+
+fn surd_string(quadratic_part: &[u8; PRIMES.len()], quadratic_power: u8) -> String {
+    format_surd(
+        quadratic_part,
+        quadratic_power,
+        |radicand, inner| match inner {
+            None => format!("sqrt({})", radicand),
+            Some(inner) if radicand.is_one() => format!("sqrt({})", inner),
+            Some(inner) => format!("sqrt({}*{})", radicand, inner),
+        },
+    )
+}
+
+fn surd_latex_string(quadratic_part: &[u8; PRIMES.len()], quadratic_power: u8) -> String {
+    format_surd(
+        quadratic_part,
+        quadratic_power,
+        |radicand, inner| match inner {
+            None => format!("\\sqrt{{{}}}", radicand),
+            Some(inner) if radicand.is_one() => format!("\\sqrt{{{}}}", inner),
+            Some(inner) => format!("\\sqrt{{{}{}}}", radicand, inner),
+        },
+    )
+}
+
+fn surd_unicode_string(quadratic_part: &[u8; PRIMES.len()], quadratic_power: u8) -> String {
+    format_surd(
+        quadratic_part,
+        quadratic_power,
+        |radicand, inner| match inner {
+            None => format!("√{}", radicand),
+            Some(inner) if radicand.is_one() => format!("√{}", inner),
+            Some(inner) => format!("√({}×{})", radicand, inner),
+        },
+    )
+}
+
+// Irrational surds have no ties, and `floor(2 * |numerator| * surd)` is an exact integer root.
+fn round_surd(
+    numerator: i64,
+    denominator: i64,
+    quadratic_part: &[u8; PRIMES.len()],
+    quadratic_power: u8,
+) -> Option<i64> {
+    let exponent = 1u32 << quadratic_power;
+    let scaled = ((BigUint::from(numerator.unsigned_abs()) * 2u32).pow(exponent)
+        * number_under_sqrt(quadratic_part))
+    .nth_root(exponent);
+    let denominator = BigUint::from(denominator as u64);
+    let rounded = i64::try_from((scaled + &denominator) / (denominator * 2u32)).ok()?;
+    Some(if numerator < 0 { -rounded } else { rounded })
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "IntegralQuadraticFields"))]
 pub struct IntegralQuadratic {
     integral_part: i64,
     quadratic_part: [u8; PRIMES.len()],
     quadratic_power: u8,
 }
 
-#[derive(Clone, Copy, Eq, Hash, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "RationalQuadraticFields"))]
 pub struct RationalQuadratic {
     rational_part: Rational64,
     quadratic_part: [u8; PRIMES.len()],
     quadratic_power: u8,
 }
 
-pub struct ParseQuadraticError {}
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct IntegralQuadraticFields {
+    integral_part: i64,
+    quadratic_part: [u8; PRIMES.len()],
+    quadratic_power: u8,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct RationalQuadraticFields {
+    rational_part: Rational64,
+    quadratic_part: [u8; PRIMES.len()],
+    quadratic_power: u8,
+}
+
+#[cfg(feature = "serde")]
+fn is_reduced_surd(quadratic_part: &[u8; PRIMES.len()], quadratic_power: u8) -> bool {
+    quadratic_power <= MAX_QUADRATIC_POWER
+        && quadratic_part
+            .iter()
+            .all(|power| u32::from(*power) < 1 << quadratic_power)
+        && (quadratic_power == 0 || quadratic_part.iter().any(|power| power % 2 == 1))
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<IntegralQuadraticFields> for IntegralQuadratic {
+    type Error = &'static str;
+
+    fn try_from(fields: IntegralQuadraticFields) -> Result<Self, Self::Error> {
+        if !is_reduced_surd(&fields.quadratic_part, fields.quadratic_power)
+            || fields.integral_part == 0 && fields.quadratic_power > 0
+        {
+            return Err("malformed quadratic part");
+        }
+        Ok(Self {
+            integral_part: fields.integral_part,
+            quadratic_part: fields.quadratic_part,
+            quadratic_power: fields.quadratic_power,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<RationalQuadraticFields> for RationalQuadratic {
+    type Error = &'static str;
+
+    fn try_from(fields: RationalQuadraticFields) -> Result<Self, Self::Error> {
+        let (numerator, denominator) =
+            (*fields.rational_part.numer(), *fields.rational_part.denom());
+        if denominator <= 0 || numerator.gcd(&denominator) != 1 {
+            return Err("rational part is not in lowest terms");
+        }
+        if !is_reduced_surd(&fields.quadratic_part, fields.quadratic_power)
+            || numerator == 0 && fields.quadratic_power > 0
+        {
+            return Err("malformed quadratic part");
+        }
+        Ok(Self {
+            rational_part: fields.rational_part,
+            quadratic_part: fields.quadratic_part,
+            quadratic_power: fields.quadratic_power,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ParseQuadraticError {
+    Empty,
+    InvalidRadix,
+    MalformedFraction,
+    UnrepresentableSurd,
+}
@@ -1,13 +1,27 @@
-use super::{ParseQuadraticError, RationalQuadratic, PRIMES};
-use crate::number_theory::try_sqrt;
-use crate::Number;
+use super::{
+    round_surd, surd_latex_string, surd_string, surd_unicode_string, ParseQuadraticError,
+    RationalQuadratic, MAX_QUADRATIC_POWER, PRIMES,
+};
+use crate::number_theory::{round_rational, try_nth_root, try_sqrt};
+use crate::{DisplayStyle, Number};
 use num::rational::Rational64;
-use num::traits::{Inv, Pow};
-use num::{Integer, Num, One, Signed, Zero};
+use num::traits::{CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, Inv, Pow};
+use num::{checked_pow, Integer, Num, One, Signed, Zero};
 use opimps;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::fmt;
+use std::num::TryFromIntError;
 use std::ops::{Add, Div, Mul, Neg, Rem, Sub};
 
+const SQRT_CACHE_CAPACITY: usize = 1 << 16;
+
+thread_local! {
+    static SQRT_CACHE: RefCell<HashMap<RationalQuadratic, Option<RationalQuadratic>>> =
+        RefCell::new(HashMap::new());
+}
+
 impl RationalQuadratic {
     #[inline]
     pub fn rational_part(&self) -> Rational64 {
@@ -23,6 +37,66 @@ impl RationalQuadratic {
     pub fn quadratic_power(&self) -> u8 {
         self.quadratic_power
     }
+
+    pub fn to_f64(&self) -> f64 {
+        let mut result = *self.rational_part.numer() as f64 / *self.rational_part.denom() as f64;
+        for (prime, power) in PRIMES.iter().zip(self.quadratic_part.iter()) {
+            if *power > 0 {
+                result *=
+                    (*prime as f64).powf(*power as f64 / 2f64.powi(self.quadratic_power as i32));
+            }
+        }
+        result
+    }
+
+    pub fn round(&self) -> Option<i64> {
+        if self.quadratic_power == 0 {
+            round_rational(self.rational_part)
+        } else {
+            round_surd(
+                *self.rational_part.numer(),
+                *self.rational_part.denom(),
+                &self.quadratic_part,
+                self.quadratic_power,
+            )
+        }
+    }
+
+    pub fn format_with(&self, style: DisplayStyle) -> String {
+        if style != DisplayStyle::Unicode || self.is_rational() {
+            return self.to_string();
+        }
+        let quadratic_string = surd_unicode_string(&self.quadratic_part, self.quadratic_power);
+        if *self.rational_part.denom() == 1 {
+            if *self.rational_part.numer() == 1 {
+                return quadratic_string;
+            } else if *self.rational_part.numer() == -1 {
+                return format!("-{}", quadratic_string);
+            }
+        }
+        format!("{}×{}", self.rational_part, quadratic_string)
+    }
+
+    pub fn to_latex_string(&self) -> String {
+        let numerator = *self.rational_part.numer();
+        let denominator = *self.rational_part.denom();
+        let rational_string = if denominator == 1 {
+            numerator.to_string()
+        } else if numerator < 0 {
+            format!("-\\frac{{{}}}{{{}}}", -numerator, denominator)
+        } else {
+            format!("\\frac{{{}}}{{{}}}", numerator, denominator)
+        };
+        if self.is_rational() {
+            return rational_string;
+        }
+        let quadratic_string = surd_latex_string(&self.quadratic_part, self.quadratic_power);
+        match (numerator, denominator) {
+            (1, 1) => quadratic_string,
+            (-1, 1) => "-".to_string() + &quadratic_string,
+            _ => rational_string + &quadratic_string,
+        }
+    }
 }
 
 impl fmt::Display for RationalQuadratic {
@@ -30,16 +104,7 @@ impl fmt::Display for RationalQuadratic {
         if self.is_rational() {
             write!(f, "{}", self.rational_part)
         } else {
-            let mut number_under_sqrt = 1;
-            for i in 0..PRIMES.len() {
-                number_under_sqrt *= PRIMES[i].pow(self.quadratic_part[i] as u32);
-            }
-            let quadratic_string = format!(
-                "{}{}{}",
-                "sqrt(".repeat(self.quadratic_power as usize),
-                number_under_sqrt,
-                ")".repeat(self.quadratic_power as usize)
-            );
+            let quadratic_string = surd_string(&self.quadratic_part, self.quadratic_power);
             if *self.rational_part.denom() == 1 {
                 if *self.rational_part.numer() == 1 {
                     return write!(f, "{}", quadratic_string);
@@ -63,6 +128,15 @@ impl From<i64> for RationalQuadratic {
     }
 }
 
+impl TryFrom<i128> for RationalQuadratic {
+    type Error = TryFromIntError;
+
+    #[inline]
+    fn try_from(x: i128) -> Result<Self, Self::Error> {
+        Ok(i64::try_from(x)?.into())
+    }
+}
+
 impl From<Rational64> for RationalQuadratic {
     #[inline]
     fn from(x: Rational64) -> Self {
@@ -93,13 +167,96 @@ impl Number for RationalQuadratic {
     fn is_rational(self) -> bool {
         self.quadratic_power == 0
     }
+
+    #[inline]
+    fn try_sqrt(self) -> Option<Self> {
+        RationalQuadratic::try_sqrt(&self)
+    }
+
+    #[inline]
+    fn try_nth_root(self, n: u32) -> Option<Self> {
+        RationalQuadratic::try_nth_root(&self, n)
+    }
+
+    #[inline]
+    fn to_f64(self) -> f64 {
+        RationalQuadratic::to_f64(&self)
+    }
+
+    #[inline]
+    fn quadratic_power(self) -> u8 {
+        self.quadratic_power
+    }
 }
 
 impl Num for RationalQuadratic {
     type FromStrRadixErr = ParseQuadraticError;
 
-    fn from_str_radix(_str: &str, _radix: u32) -> Result<Self, Self::FromStrRadixErr> {
-        Err(ParseQuadraticError {})
+    fn from_str_radix(str: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+        let str = str.trim();
+        if str.is_empty() {
+            return Err(ParseQuadraticError::Empty);
+        } else if !(2..=36).contains(&radix) {
+            return Err(ParseQuadraticError::InvalidRadix);
+        }
+        let (coefficient, surd) = match str.find("sqrt(") {
+            None => (str, None),
+            Some(0) => ("1", Some(str)),
+            Some(index) => match &str[..index] {
+                "-" => ("-1", Some(&str[index..])),
+                prefix if prefix.ends_with('*') => {
+                    (&prefix[..prefix.len() - 1], Some(&str[index..]))
+                }
+                _ => return Err(ParseQuadraticError::MalformedFraction),
+            },
+        };
+        let coefficient = if coefficient.contains('/') {
+            Rational64::from_str_radix(coefficient, radix).ok()
+        } else {
+            i64::from_str_radix(coefficient, radix)
+                .ok()
+                .map(Rational64::from)
+        }
+        .ok_or(ParseQuadraticError::MalformedFraction)?;
+        let result = match surd {
+            Some(surd) => Self::parse_surd(surd, radix)?,
+            None => return Ok(coefficient.into()),
+        };
+        result
+            .checked_mul(&coefficient.into())
+            .ok_or(ParseQuadraticError::UnrepresentableSurd)
+    }
+}
+
+impl RationalQuadratic {
+    // Parses `sqrt(F1*sqrt(F2*...sqrt(Fk)))`, where any level may omit its integer factor.
+    fn parse_surd(surd: &str, radix: u32) -> Result<Self, ParseQuadraticError> {
+        if !surd.starts_with("sqrt(") || !surd.ends_with(')') {
+            return Err(ParseQuadraticError::MalformedFraction);
+        }
+        let inner = &surd[5..surd.len() - 1];
+        let (factor, inner_surd) = match inner.find("sqrt(") {
+            None => (inner, None),
+            Some(0) => ("1", Some(inner)),
+            Some(index) => match inner[..index].strip_suffix('*') {
+                Some(factor) => (factor, Some(&inner[index..])),
+                None => return Err(ParseQuadraticError::MalformedFraction),
+            },
+        };
+        let factor = i64::from_str_radix(factor, radix)
+            .map_err(|_| ParseQuadraticError::MalformedFraction)?;
+        let radicand = match inner_surd {
+            Some(inner_surd) => Self::parse_surd(inner_surd, radix)?
+                .checked_mul(&factor.into())
+                .ok_or(ParseQuadraticError::UnrepresentableSurd)?,
+            None => factor.into(),
+        };
+        if radicand.quadratic_power >= MAX_QUADRATIC_POWER {
+            return Err(ParseQuadraticError::UnrepresentableSurd);
+        }
+        radicand
+            .try_sqrt()
+            .ok_or(ParseQuadraticError::UnrepresentableSurd)
     }
 }
 
@@ -260,6 +417,7 @@ fn mul(self: RationalQuadratic, rhs: RationalQuadratic) -> RationalQuadratic {
     }
     let mut quadratic_part = [0; PRIMES.len()];
     let mut quadratic_power = u8::max(self.quadratic_power, rhs.quadratic_power);
+    debug_assert!(quadratic_power <= MAX_QUADRATIC_POWER);
     if quadratic_power > 0 {
         for i in 0..PRIMES.len() {
             quadratic_part[i] = (self.quadratic_part[i]
@@ -362,6 +520,7 @@ fn div(self: RationalQuadratic, rhs: RationalQuadratic) -> RationalQuadratic {
     }
     let mut quadratic_part = [0; PRIMES.len()];
     let mut quadratic_power = u8::max(self.quadratic_power, rhs.quadratic_power);
+    debug_assert!(quadratic_power <= MAX_QUADRATIC_POWER);
     if quadratic_power > 0 {
         for i in 0..PRIMES.len() {
             let x = self.quadratic_part[i] << (quadratic_power - self.quadratic_power);
@@ -407,6 +566,113 @@ fn div(self: RationalQuadratic, rhs: Rational64) -> RationalQuadratic {
     }
 }
 
+impl CheckedAdd for RationalQuadratic {
+    fn checked_add(&self, rhs: &Self) -> Option<Self> {
+        if self.is_zero() {
+            Some(*rhs)
+        } else if rhs.is_zero() {
+            Some(*self)
+        } else if self.quadratic_power != rhs.quadratic_power
+            || self.quadratic_part != rhs.quadratic_part
+        {
+            None
+        } else {
+            let rational_part = self.rational_part.checked_add(&rhs.rational_part)?;
+            if rational_part.is_zero() {
+                Some(RationalQuadratic::zero())
+            } else {
+                Some(RationalQuadratic {
+                    rational_part,
+                    quadratic_part: self.quadratic_part,
+                    quadratic_power: self.quadratic_power,
+                })
+            }
+        }
+    }
+}
+
+impl CheckedSub for RationalQuadratic {
+    #[inline]
+    fn checked_sub(&self, rhs: &Self) -> Option<Self> {
+        self.checked_add(&-rhs)
+    }
+}
+
+impl CheckedMul for RationalQuadratic {
+    fn checked_mul(&self, rhs: &Self) -> Option<Self> {
+        let mut rational_part = self.rational_part.checked_mul(&rhs.rational_part)?;
+        if rational_part.is_zero() {
+            return Some(RationalQuadratic::zero());
+        }
+        let mut quadratic_part = [0; PRIMES.len()];
+        let mut quadratic_power = u8::max(self.quadratic_power, rhs.quadratic_power);
+        if quadratic_power > MAX_QUADRATIC_POWER {
+            return None;
+        }
+        if quadratic_power > 0 {
+            for i in 0..PRIMES.len() {
+                quadratic_part[i] = (self.quadratic_part[i]
+                    << (quadratic_power - self.quadratic_power))
+                    + (rhs.quadratic_part[i] << (quadratic_power - rhs.quadratic_power));
+            }
+            for (prime, power) in PRIMES.iter().zip(quadratic_part.iter_mut()) {
+                if *power >= 1 << quadratic_power {
+                    *power &= (1 << quadratic_power) - 1;
+                    rational_part = rational_part.checked_mul(&Rational64::from(*prime))?;
+                }
+            }
+            while quadratic_power > 0 && quadratic_part.iter().all(|x| x % 2 == 0) {
+                quadratic_power -= 1;
+                for x in quadratic_part.iter_mut() {
+                    *x >>= 1;
+                }
+            }
+        }
+        Some(RationalQuadratic {
+            rational_part,
+            quadratic_part,
+            quadratic_power,
+        })
+    }
+}
+
+impl CheckedDiv for RationalQuadratic {
+    fn checked_div(&self, rhs: &Self) -> Option<Self> {
+        let mut rational_part = self.rational_part.checked_div(&rhs.rational_part)?;
+        if rational_part.is_zero() {
+            return Some(RationalQuadratic::zero());
+        }
+        let mut quadratic_part = [0; PRIMES.len()];
+        let mut quadratic_power = u8::max(self.quadratic_power, rhs.quadratic_power);
+        if quadratic_power > MAX_QUADRATIC_POWER {
+            return None;
+        }
+        if quadratic_power > 0 {
+            for i in 0..PRIMES.len() {
+                let x = self.quadratic_part[i] << (quadratic_power - self.quadratic_power);
+                let y = rhs.quadratic_part[i] << (quadratic_power - rhs.quadratic_power);
+                if x < y {
+                    rational_part = rational_part.checked_div(&Rational64::from(PRIMES[i]))?;
+                    quadratic_part[i] = (1 << quadratic_power) + x - y;
+                } else {
+                    quadratic_part[i] = x - y;
+                }
+            }
+            while quadratic_power > 0 && quadratic_part.iter().all(|x| x % 2 == 0) {
+                quadratic_power -= 1;
+                for x in quadratic_part.iter_mut() {
+                    *x >>= 1;
+                }
+            }
+        }
+        Some(RationalQuadratic {
+            rational_part,
+            quadratic_part,
+            quadratic_power,
+        })
+    }
+}
+
 #[opimps::impl_ops(Rem)]
 #[inline]
 fn rem(self: RationalQuadratic, _rhs: RationalQuadratic) -> RationalQuadratic {
@@ -452,7 +718,60 @@ impl Pow<&i32> for RationalQuadratic {
 }
 
 impl RationalQuadratic {
+    pub fn checked_pow(&self, power: i32) -> Option<Self> {
+        if power == 0 {
+            return Some(Self::one());
+        } else if power < 0 {
+            return Self::one().checked_div(&self.checked_pow(power.checked_neg()?)?);
+        }
+        let mut rational_part = checked_pow(self.rational_part, power as usize)?;
+        let mut quadratic_part = [0u8; PRIMES.len()];
+        let mut quadratic_power = self.quadratic_power;
+        let mut power = power;
+        while quadratic_power > 0 && power % 2 == 0 {
+            quadratic_power -= 1;
+            power >>= 1;
+        }
+        for i in 0..PRIMES.len() {
+            let prime_power = ((self.quadratic_part[i] as i32).checked_mul(power)?)
+                .div_mod_floor(&(1 << quadratic_power));
+            rational_part = rational_part.checked_mul(&Rational64::from(checked_pow(
+                PRIMES[i],
+                prime_power.0 as usize,
+            )?))?;
+            quadratic_part[i] = prime_power.1 as u8;
+        }
+        Some(Self {
+            rational_part,
+            quadratic_part,
+            quadratic_power,
+        })
+    }
+
+    pub fn pow_rational(&self, exponent: Rational64) -> Option<Self> {
+        let root = self.try_nth_root(u32::try_from(*exponent.denom()).ok()?)?;
+        if root.quadratic_power > MAX_QUADRATIC_POWER {
+            return None;
+        }
+        root.checked_pow(i32::try_from(*exponent.numer()).ok()?)
+    }
+
     pub fn try_sqrt(&self) -> Option<Self> {
+        SQRT_CACHE.with(|cache| {
+            if let Some(result) = cache.borrow().get(self) {
+                return *result;
+            }
+            let result = self.factor_sqrt();
+            let mut cache = cache.borrow_mut();
+            if cache.len() >= SQRT_CACHE_CAPACITY {
+                cache.clear();
+            }
+            cache.insert(*self, result);
+            result
+        })
+    }
+
+    fn factor_sqrt(&self) -> Option<Self> {
         if self.rational_part.is_zero() {
             return Some(*self);
         } else if self.rational_part.is_negative() {
@@ -488,6 +807,8 @@ impl RationalQuadratic {
         denominator *= try_sqrt(q)?;
         if quadratic_part.iter().all(|x| *x == 0) {
             quadratic_power = 0;
+        } else if quadratic_power > MAX_QUADRATIC_POWER {
+            return None;
         }
         Some(Self {
             rational_part: Rational64::new_raw(numerator, denominator),
@@ -495,4 +816,183 @@ impl RationalQuadratic {
             quadratic_power,
         })
     }
+
+    pub fn try_nth_root(&self, n: u32) -> Option<Self> {
+        if n == 0 {
+            return None;
+        } else if n == 1 || self.rational_part.is_zero() {
+            return Some(*self);
+        } else if n.is_multiple_of(2) {
+            return self.try_sqrt()?.try_nth_root(n / 2);
+        } else if self.rational_part.is_negative() {
+            return Some(-(-self).try_nth_root(n)?);
+        }
+        let mut p = *self.rational_part.numer();
+        let mut q = *self.rational_part.denom();
+        let mut quadratic_part = [0; PRIMES.len()];
+        let mut quadratic_power = self.quadratic_power;
+        let mut numerator = 1i64;
+        let mut denominator = 1i64;
+        for i in 0..PRIMES.len() {
+            let prime = PRIMES[i];
+            let mut exponent = 0i64;
+            while p % prime == 0 {
+                exponent += 1;
+                p /= prime;
+            }
+            while q % prime == 0 {
+                exponent -= 1;
+                q /= prime;
+            }
+            let total = (exponent << quadratic_power) + self.quadratic_part[i] as i64;
+            if total % n as i64 != 0 {
+                return None;
+            }
+            let (whole, fraction) =
+                Integer::div_mod_floor(&(total / n as i64), &(1 << quadratic_power));
+            quadratic_part[i] = fraction as u8;
+            if whole >= 0 {
+                numerator = numerator.checked_mul(checked_pow(prime, whole as usize)?)?;
+            } else {
+                denominator = denominator.checked_mul(checked_pow(prime, -whole as usize)?)?;
+            }
+        }
+        numerator = numerator.checked_mul(try_nth_root(p, n)?)?;
+        denominator = denominator.checked_mul(try_nth_root(q, n)?)?;
+        while quadratic_power > 0 && quadratic_part.iter().all(|x| x % 2 == 0) {
+            quadratic_power -= 1;
+            for x in quadratic_part.iter_mut() {
+                *x >>= 1;
+            }
+        }
+        Some(Self {
+            rational_part: Rational64::new(numerator, denominator),
+            quadratic_part,
+            quadratic_power,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Limits, Solver};
+
+    fn cache_size() -> usize {
+        SQRT_CACHE.with(|cache| cache.borrow().len())
+    }
+
+    #[test]
+    fn memoized_sqrt_matches_factorization() {
+        for p in -50..=200 {
+            for q in 1..=12 {
+                let x = RationalQuadratic::from(Rational64::new(p, q));
+                assert_eq!(x.try_sqrt(), x.factor_sqrt());
+                assert_eq!(x.try_sqrt(), x.factor_sqrt());
+                if let Some(y) = x.try_sqrt() {
+                    assert_eq!(y.try_sqrt(), y.factor_sqrt());
+                }
+            }
+        }
+    }
+
+    fn nested_sqrt(x: i64, power: u8) -> RationalQuadratic {
+        (0..power).fold(RationalQuadratic::from(x), |x, _| x.try_sqrt().unwrap())
+    }
+
+    #[test]
+    fn displays_each_quadratic_power() {
+        let cases = [
+            (nested_sqrt(2, 1), "sqrt(2)"),
+            (nested_sqrt(2, 2), "sqrt(sqrt(2))"),
+            (nested_sqrt(2, 3), "sqrt(sqrt(sqrt(2)))"),
+            (nested_sqrt(12, 1), "2*sqrt(3)"),
+            (nested_sqrt(16, 2), "2"),
+            (
+                -nested_sqrt(6, 3) / RationalQuadratic::from(5),
+                "-1/5*sqrt(sqrt(sqrt(6)))",
+            ),
+        ];
+        for (x, expected) in cases.iter() {
+            assert_eq!(x.to_string(), *expected);
+            assert_eq!(RationalQuadratic::from_str_radix(expected, 10), Ok(*x));
+        }
+    }
+
+    #[test]
+    fn displayed_surds_evaluate_to_their_value() {
+        for power in 1..=3 {
+            for x in [2, 3, 6, 10, 12, 72].iter() {
+                let surd = nested_sqrt(*x, power);
+                let parsed = RationalQuadratic::from_str_radix(&surd.to_string(), 10).unwrap();
+                let expected = (*x as f64).powf(0.5f64.powi(power as i32));
+                assert!((parsed.to_f64() - expected).abs() < 1e-12 * expected);
+            }
+        }
+    }
+
+    #[test]
+    fn nth_roots_must_be_exact() {
+        let x = |n: i64| RationalQuadratic::from(n);
+        assert_eq!(x(16).try_nth_root(4), Some(x(2)));
+        assert_eq!(x(2).try_nth_root(3), None);
+        assert_eq!(x(64).try_nth_root(6), Some(x(2)));
+        assert_eq!(x(-8).try_nth_root(3), Some(x(-2)));
+        assert_eq!(x(-16).try_nth_root(4), None);
+        assert_eq!(x(8).try_sqrt().unwrap().try_nth_root(3), x(2).try_sqrt());
+        assert_eq!(x(2).try_nth_root(4), nested_sqrt(2, 2).into());
+        let eighth = RationalQuadratic::from(Rational64::new(1, 8));
+        assert_eq!(eighth.try_nth_root(3), Some(Rational64::new(1, 2).into()));
+    }
+
+    #[test]
+    fn rational_powers_must_be_representable() {
+        let x = |n: i64| RationalQuadratic::from(n);
+        let pow = |n: i64, p: i64, q: i64| x(n).pow_rational(Rational64::new(p, q));
+        assert_eq!(pow(8, 1, 3), Some(x(2)));
+        assert_eq!(pow(2, 3, 2), Some(x(2) * nested_sqrt(2, 1)));
+        assert_eq!(pow(2, 1, 2), Some(nested_sqrt(2, 1)));
+        assert_eq!(pow(4, -1, 2), Some(Rational64::new(1, 2).into()));
+        assert_eq!(pow(2, 1, 3), None);
+        assert_eq!(pow(-4, 1, 2), None);
+    }
+
+    #[test]
+    fn unicode_snapshots() {
+        let x = nested_sqrt(2, 3) * RationalQuadratic::from(3);
+        assert_eq!(x.to_string(), "3*sqrt(sqrt(sqrt(2)))");
+        assert_eq!(x.format_with(DisplayStyle::Unicode), "3×√√√2");
+        let y = nested_sqrt(2, 2) * RationalQuadratic::from(Rational64::new(1, 3));
+        assert_eq!(y.to_string(), "1/3*sqrt(sqrt(2))");
+        assert_eq!(y.format_with(DisplayStyle::Unicode), "1/3×√√2");
+    }
+
+    #[test]
+    fn latex_snapshots() {
+        let sqrt_2 = nested_sqrt(2, 1);
+        assert_eq!((-sqrt_2).to_latex_string(), "-\\sqrt{2}");
+        assert_eq!(
+            (sqrt_2 * RationalQuadratic::from(Rational64::new(-3, 2))).to_latex_string(),
+            "-\\frac{3}{2}\\sqrt{2}"
+        );
+        assert_eq!(
+            nested_sqrt(2, 3).to_latex_string(),
+            "\\sqrt{\\sqrt{\\sqrt{2}}}"
+        );
+    }
+
+    #[test]
+    fn repeated_solve_hits_the_sqrt_cache() {
+        let limits = Limits {
+            max_digits: 20,
+            max_factorial: 9,
+            max_quadratic_power: 2,
+            ..Limits::default()
+        };
+        Solver::<RationalQuadratic>::new(5, limits.clone()).solve(99.into(), Some(4));
+        let size = cache_size();
+        assert!(size > 0);
+        Solver::<RationalQuadratic>::new(5, limits).solve(99.into(), Some(4));
+        assert_eq!(cache_size(), size);
+    }
 }
@@ -1,7 +1,8 @@
 use super::{Limits, Solver};
-use crate::{Expression, Number, RationalQuadratic};
+use crate::{Expression, Number, RationalQuadratic, Rc};
 use num::rational::Rational64;
-use std::rc::Rc;
+use std::collections::{HashMap, VecDeque};
+use std::convert::TryFrom;
 
 enum ReusableSearchState {
     None,
@@ -12,8 +13,9 @@ enum ReusableSearchState {
 }
 
 pub struct ReusableSolver {
-    target: i64,
-    integral_solver: Solver<i64>,
+    target: Option<i128>,
+    free_negation: bool,
+    integral_solver: Solver<i128>,
     rational_solver: Solver<Rational64>,
     rational_quadratic_solver: Solver<RationalQuadratic>,
     depth_searched: usize,
@@ -28,8 +30,9 @@ impl ReusableSolver {
         quadratic_limits: Limits,
     ) -> Self {
         Self {
-            target: 0,
-            integral_solver: Solver::<i64>::new_progressive(n, integral_limits),
+            target: None,
+            free_negation: integral_limits.free_negation,
+            integral_solver: Solver::<i128>::new_progressive(n, integral_limits),
             rational_solver: Solver::<Rational64>::new_progressive(n, rational_limits),
             rational_quadratic_solver: Solver::<RationalQuadratic>::new_progressive(
                 n,
@@ -42,11 +45,16 @@ impl ReusableSolver {
 
     pub fn solve(
         &mut self,
-        target: i64,
+        target: i128,
         max_depth: Option<usize>,
     ) -> Option<(Rc<Expression>, usize)> {
-        self.target = target;
-        if let Some((expression, digits)) = self.get_solution(&self.target) {
+        if self.free_negation && target < 0 {
+            return self
+                .solve(-target, max_depth)
+                .map(|(expression, digits)| (Expression::from_negate(expression), digits));
+        }
+        self.target = Some(target);
+        if let Some((expression, digits)) = self.get_solution(&target) {
             return if max_depth.unwrap_or(usize::MAX) >= *digits {
                 Some((expression.clone(), *digits))
             } else {
@@ -54,18 +62,103 @@ impl ReusableSolver {
             };
         }
         for digits in self.depth_searched + 1..=max_depth.unwrap_or(usize::MAX) {
+            if self.is_exhausted() {
+                break;
+            }
             if self.search(digits) {
-                return Some(self.get_solution(&self.target)?.clone());
+                return Some(self.get_solution(&target)?.clone());
             }
         }
         None
     }
 
-    pub fn get_solution(&self, x: &i64) -> Option<&(Rc<Expression>, usize)> {
+    pub fn solve_stream(
+        &mut self,
+        targets: Vec<i128>,
+        max_depth: Option<usize>,
+    ) -> SolutionStream<'_> {
+        SolutionStream {
+            solver: self,
+            targets,
+            max_depth,
+            solutions: VecDeque::new(),
+        }
+    }
+
+    pub fn solvability_map(
+        &mut self,
+        targets: &[i128],
+        max_depth: Option<usize>,
+    ) -> Vec<(i128, Option<usize>)> {
+        let depths: HashMap<i128, usize> = self
+            .solve_stream(targets.to_vec(), max_depth)
+            .map(|(target, _, digits)| (target, digits))
+            .collect();
+        targets
+            .iter()
+            .map(|target| {
+                let digits = depths
+                    .get(target)
+                    .copied()
+                    .filter(|digits| *digits <= max_depth.unwrap_or(usize::MAX));
+                (*target, digits)
+            })
+            .collect()
+    }
+
+    pub fn min_digits_table(&mut self, max_target: i64, max_depth: usize) -> Vec<Option<usize>> {
+        let targets: Vec<i128> = (1..=max_target as i128).collect();
+        self.solvability_map(&targets, Some(max_depth))
+            .into_iter()
+            .map(|(_, digits)| digits)
+            .collect()
+    }
+
+    pub fn solve_many(
+        &mut self,
+        targets: &[i128],
+        max_depth: Option<usize>,
+    ) -> HashMap<i128, (Rc<Expression>, usize)> {
+        self.solvability_map(targets, max_depth)
+            .into_iter()
+            .filter_map(|(target, digits)| {
+                digits?;
+                Some((target, self.find_solution(target)?))
+            })
+            .collect()
+    }
+
+    pub fn get_solution(&self, x: &i128) -> Option<&(Rc<Expression>, usize)> {
+        let narrow_x = i64::try_from(*x).ok();
         self.integral_solver
             .get_solution(x)
-            .or_else(|| self.rational_solver.get_solution(&(*x).into()))
-            .or_else(|| self.rational_quadratic_solver.get_solution(&(*x).into()))
+            .or_else(|| self.rational_solver.get_solution(&narrow_x?.into()))
+            .or_else(|| {
+                self.rational_quadratic_solver
+                    .get_solution(&narrow_x?.into())
+            })
+    }
+
+    fn grow_to_depth(&mut self, depth: usize) {
+        self.target = None;
+        while self.depth_searched < depth {
+            self.search(self.depth_searched + 1);
+        }
+    }
+
+    fn is_exhausted(&self) -> bool {
+        self.integral_solver.is_exhausted()
+            && self.rational_solver.is_exhausted()
+            && self.rational_quadratic_solver.is_exhausted()
+    }
+
+    fn find_solution(&self, target: i128) -> Option<(Rc<Expression>, usize)> {
+        if self.free_negation && target < 0 {
+            let (expression, digits) = self.get_solution(&-target)?;
+            Some((Expression::from_negate(expression.clone()), *digits))
+        } else {
+            self.get_solution(&target).cloned()
+        }
     }
 
     fn search(&mut self, digits: usize) -> bool {
@@ -77,18 +170,22 @@ impl ReusableSolver {
         }
         match self.search_state {
             ReusableSearchState::Integral => {
-                if self
-                    .integral_solver
-                    .solve(self.target, Some(digits))
-                    .is_some()
-                {
-                    return true;
+                if let Some(target) = self.target {
+                    if self.integral_solver.solve(target, Some(digits)).is_some() {
+                        return true;
+                    }
+                } else {
+                    self.integral_solver.grow_to_depth(digits);
                 }
                 for (x, expression, _) in self.integral_solver.new_numbers() {
+                    let x = match i64::try_from(*x) {
+                        Ok(x) => x,
+                        Err(_) => continue,
+                    };
                     self.rational_solver
-                        .try_insert((*x).into(), digits, || expression.clone());
+                        .try_insert(x.into(), digits, || expression.clone());
                     self.rational_quadratic_solver
-                        .try_insert((*x).into(), digits, || expression.clone());
+                        .try_insert(x.into(), digits, || expression.clone());
                 }
                 self.clear_new_numbers();
                 self.search_state = ReusableSearchState::Rational;
@@ -97,17 +194,21 @@ impl ReusableSolver {
         }
         match self.search_state {
             ReusableSearchState::Rational => {
-                if self
-                    .rational_solver
-                    .solve(self.target.into(), Some(digits))
-                    .is_some()
-                {
-                    return true;
+                if let Some(target) = self.target.and_then(|x| i64::try_from(x).ok()) {
+                    if self
+                        .rational_solver
+                        .solve(target.into(), Some(digits))
+                        .is_some()
+                    {
+                        return true;
+                    }
+                } else {
+                    self.rational_solver.grow_to_depth(digits);
                 }
                 for (x, expression, _) in self.rational_solver.new_numbers() {
                     if let Some(x_int) = x.to_int() {
                         self.integral_solver
-                            .try_insert(x_int, digits, || expression.clone());
+                            .try_insert(x_int.into(), digits, || expression.clone());
                     }
                     self.rational_quadratic_solver
                         .try_insert((*x).into(), digits, || expression.clone());
@@ -119,17 +220,21 @@ impl ReusableSolver {
         }
         match self.search_state {
             ReusableSearchState::RationalQuadratic => {
-                if self
-                    .rational_quadratic_solver
-                    .solve(self.target.into(), Some(digits))
-                    .is_some()
-                {
-                    return true;
+                if let Some(target) = self.target.and_then(|x| i64::try_from(x).ok()) {
+                    if self
+                        .rational_quadratic_solver
+                        .solve(target.into(), Some(digits))
+                        .is_some()
+                    {
+                        return true;
+                    }
+                } else {
+                    self.rational_quadratic_solver.grow_to_depth(digits);
                 }
                 for (x, expression, _) in self.rational_quadratic_solver.new_numbers() {
                     if let Some(x_int) = x.to_int() {
                         self.integral_solver
-                            .try_insert(x_int, digits, || expression.clone());
+                            .try_insert(x_int.into(), digits, || expression.clone());
                     }
                     if x.is_rational() {
                         self.rational_solver
@@ -152,3 +257,43 @@ impl ReusableSolver {
         self.rational_quadratic_solver.clear_new_numbers();
     }
 }
+
+pub struct SolutionStream<'a> {
+    solver: &'a mut ReusableSolver,
+    targets: Vec<i128>,
+    max_depth: Option<usize>,
+    solutions: VecDeque<(i128, Rc<Expression>, usize)>,
+}
+
+impl<'a> Iterator for SolutionStream<'a> {
+    type Item = (i128, Rc<Expression>, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(solution) = self.solutions.pop_front() {
+                return Some(solution);
+            }
+            if self.targets.is_empty() {
+                return None;
+            }
+            let solver = &*self.solver;
+            let solutions = &mut self.solutions;
+            self.targets.retain(|target| {
+                if let Some((expression, digits)) = solver.find_solution(*target) {
+                    solutions.push_back((*target, expression, digits));
+                    false
+                } else {
+                    true
+                }
+            });
+            if self.solutions.is_empty() {
+                if self.solver.depth_searched >= self.max_depth.unwrap_or(usize::MAX)
+                    || self.solver.is_exhausted()
+                {
+                    return None;
+                }
+                self.solver.grow_to_depth(self.solver.depth_searched + 1);
+            }
+        }
+    }
+}
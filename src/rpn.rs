@@ -0,0 +1,259 @@
+use crate::{Expression, Rc};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum Token {
+    Number(i64),
+    Decimal(i64),
+    RepeatingDecimal(i64),
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+    Neg,
+    Sqrt(usize),
+    Factorial,
+    Average,
+    Round,
+    Subfactorial,
+    NthPrime,
+    PrimePi,
+    DoubleFactorial,
+    Root(u32),
+    Percent,
+    Log,
+    Tetration,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ParseRpnError {
+    pub position: usize,
+}
+
+impl Expression {
+    pub fn to_rpn(&self) -> Vec<Token> {
+        let mut tokens = vec![];
+        self.push_rpn(&mut tokens);
+        tokens
+    }
+
+    fn push_rpn(&self, tokens: &mut Vec<Token>) {
+        let token = match self {
+            Expression::Number(x) => Token::Number(*x),
+            Expression::Decimal(x) => Token::Decimal(*x),
+            Expression::RepeatingDecimal(x) => Token::RepeatingDecimal(*x),
+            Expression::Negate(x) => {
+                x.push_rpn(tokens);
+                Token::Neg
+            }
+            Expression::Factorial(x) => {
+                x.push_rpn(tokens);
+                Token::Factorial
+            }
+            Expression::Round(x) => {
+                x.push_rpn(tokens);
+                Token::Round
+            }
+            Expression::Subfactorial(x) => {
+                x.push_rpn(tokens);
+                Token::Subfactorial
+            }
+            Expression::NthPrime(x) => {
+                x.push_rpn(tokens);
+                Token::NthPrime
+            }
+            Expression::PrimePi(x) => {
+                x.push_rpn(tokens);
+                Token::PrimePi
+            }
+            Expression::DoubleFactorial(x) => {
+                x.push_rpn(tokens);
+                Token::DoubleFactorial
+            }
+            Expression::Sqrt(x, order) => {
+                x.push_rpn(tokens);
+                Token::Sqrt(*order)
+            }
+            Expression::Root(x, degree) => {
+                x.push_rpn(tokens);
+                Token::Root(*degree)
+            }
+            Expression::Percent(x) => {
+                x.push_rpn(tokens);
+                Token::Percent
+            }
+            Expression::Log(x, _) => {
+                x.push_rpn(tokens);
+                Token::Log
+            }
+            Expression::Add(x, y)
+            | Expression::Subtract(x, y)
+            | Expression::Multiply(x, y)
+            | Expression::Divide(x, y)
+            | Expression::Power(x, y)
+            | Expression::Tetration(x, y)
+            | Expression::Average(x, y) => {
+                x.push_rpn(tokens);
+                y.push_rpn(tokens);
+                match self {
+                    Expression::Add(_, _) => Token::Add,
+                    Expression::Subtract(_, _) => Token::Sub,
+                    Expression::Multiply(_, _) => Token::Mul,
+                    Expression::Divide(_, _) => Token::Div,
+                    Expression::Power(_, _) => Token::Pow,
+                    Expression::Tetration(_, _) => Token::Tetration,
+                    _ => Token::Average,
+                }
+            }
+        };
+        tokens.push(token);
+    }
+
+    pub fn from_rpn(tokens: &[Token], n: i64) -> Result<Rc<Expression>, ParseRpnError> {
+        let digits = n.to_string();
+        let is_repdigit = |x: i64| {
+            let x = x.to_string();
+            x.len().is_multiple_of(digits.len()) && x == digits.repeat(x.len() / digits.len())
+        };
+        let mut stack: Vec<Rc<Expression>> = vec![];
+        for (position, token) in tokens.iter().enumerate() {
+            let error = ParseRpnError { position };
+            let expression = match *token {
+                Token::Number(x) | Token::Decimal(x) | Token::RepeatingDecimal(x) => {
+                    if !is_repdigit(x) {
+                        return Err(error);
+                    }
+                    match token {
+                        Token::Number(_) => Expression::Number(x),
+                        Token::Decimal(_) => Expression::Decimal(x),
+                        _ => Expression::RepeatingDecimal(x),
+                    }
+                }
+                Token::Neg
+                | Token::Factorial
+                | Token::Round
+                | Token::Subfactorial
+                | Token::NthPrime
+                | Token::PrimePi
+                | Token::DoubleFactorial
+                | Token::Percent
+                | Token::Log
+                | Token::Sqrt(_)
+                | Token::Root(_) => {
+                    let x = stack.pop().ok_or(error)?;
+                    match *token {
+                        Token::Neg => Expression::Negate(x),
+                        Token::Factorial => Expression::Factorial(x),
+                        Token::Round => Expression::Round(x),
+                        Token::Subfactorial => Expression::Subfactorial(x),
+                        Token::NthPrime => Expression::NthPrime(x),
+                        Token::PrimePi => Expression::PrimePi(x),
+                        Token::DoubleFactorial => Expression::DoubleFactorial(x),
+                        Token::Percent => Expression::Percent(x),
+                        Token::Log => Expression::Log(x, n),
+                        Token::Sqrt(order) if order > 0 => Expression::Sqrt(x, order),
+                        Token::Root(degree) if degree > 1 => Expression::Root(x, degree),
+                        _ => return Err(error),
+                    }
+                }
+                Token::Add
+                | Token::Sub
+                | Token::Mul
+                | Token::Div
+                | Token::Pow
+                | Token::Tetration
+                | Token::Average => {
+                    if stack.len() < 2 {
+                        return Err(error);
+                    }
+                    let y = stack.pop().unwrap();
+                    let x = stack.pop().unwrap();
+                    match *token {
+                        Token::Add => Expression::Add(x, y),
+                        Token::Sub => Expression::Subtract(x, y),
+                        Token::Mul => Expression::Multiply(x, y),
+                        Token::Div => Expression::Divide(x, y),
+                        Token::Pow => Expression::Power(x, y),
+                        Token::Tetration => Expression::Tetration(x, y),
+                        _ => Expression::Average(x, y),
+                    }
+                }
+            };
+            stack.push(Rc::new(expression));
+        }
+        match (stack.pop(), stack.is_empty()) {
+            (Some(expression), true) => Ok(expression),
+            _ => Err(ParseRpnError {
+                position: tokens.len(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_factorials() {
+        let expression = Expression::parse("(3+3)!/3!", 3).unwrap();
+        let tokens = expression.to_rpn();
+        assert_eq!(
+            tokens,
+            [
+                Token::Number(3),
+                Token::Number(3),
+                Token::Add,
+                Token::Factorial,
+                Token::Number(3),
+                Token::Factorial,
+                Token::Div,
+            ]
+        );
+        assert_eq!(Expression::from_rpn(&tokens, 3), Ok(expression));
+    }
+
+    #[test]
+    fn round_trips_nested_sqrt() {
+        let expression = Expression::parse("sqrt(sqrt(2^22)*2)-2", 2).unwrap();
+        let tokens = expression.to_rpn();
+        assert_eq!(
+            tokens,
+            [
+                Token::Number(2),
+                Token::Number(22),
+                Token::Pow,
+                Token::Sqrt(1),
+                Token::Number(2),
+                Token::Mul,
+                Token::Sqrt(1),
+                Token::Number(2),
+                Token::Sub,
+            ]
+        );
+        assert_eq!(Expression::from_rpn(&tokens, 2), Ok(expression));
+    }
+
+    #[test]
+    fn rejects_malformed_streams() {
+        let error = |position| Err(ParseRpnError { position });
+        assert_eq!(Expression::from_rpn(&[], 3), error(0));
+        assert_eq!(
+            Expression::from_rpn(&[Token::Number(3), Token::Add], 3),
+            error(1)
+        );
+        assert_eq!(Expression::from_rpn(&[Token::Neg], 3), error(0));
+        assert_eq!(
+            Expression::from_rpn(&[Token::Number(3), Token::Number(3)], 3),
+            error(2)
+        );
+        assert_eq!(Expression::from_rpn(&[Token::Number(34)], 3), error(0));
+        assert_eq!(
+            Expression::from_rpn(&[Token::Number(3), Token::Sqrt(0)], 3),
+            error(1)
+        );
+    }
+}
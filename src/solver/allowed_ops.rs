@@ -0,0 +1,121 @@
+use crate::OpKind;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::ops::{BitOr, BitOrAssign};
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct AllowedOps(u32);
+
+impl AllowedOps {
+    pub const ADD: Self = Self(1 << 0);
+    pub const SUBTRACT: Self = Self(1 << 1);
+    pub const MULTIPLY: Self = Self(1 << 2);
+    pub const DIVIDE: Self = Self(1 << 3);
+    pub const POWER: Self = Self(1 << 4);
+    pub const SQRT: Self = Self(1 << 5);
+    pub const FACTORIAL: Self = Self(1 << 6);
+    pub const CONCAT: Self = Self(1 << 7);
+    pub const AVERAGE: Self = Self(1 << 8);
+    pub const ROUND: Self = Self(1 << 9);
+    pub const SUBFACTORIAL: Self = Self(1 << 10);
+    pub const NTH_PRIME: Self = Self(1 << 11);
+    pub const PRIME_PI: Self = Self(1 << 12);
+    pub const DOUBLE_FACTORIAL: Self = Self(1 << 13);
+    pub const ROOT: Self = Self(1 << 14);
+    pub const DECIMAL: Self = Self(1 << 15);
+    pub const REPEATING_DECIMAL: Self = Self(1 << 16);
+    pub const FACTORIAL_DIVIDE: Self = Self(1 << 17);
+    pub const PERCENT: Self = Self(1 << 18);
+    pub const LOG: Self = Self(1 << 19);
+    pub const RATIONAL_POWER: Self = Self(1 << 20);
+    pub const TETRATION: Self = Self(1 << 21);
+
+    pub const STANDARD: Self = Self(
+        Self::ADD.0
+            | Self::SUBTRACT.0
+            | Self::MULTIPLY.0
+            | Self::DIVIDE.0
+            | Self::POWER.0
+            | Self::SQRT.0
+            | Self::FACTORIAL.0
+            | Self::FACTORIAL_DIVIDE.0
+            | Self::CONCAT.0,
+    );
+
+    #[inline]
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    #[inline]
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+
+    #[inline]
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    #[inline]
+    pub fn insert(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+
+    #[inline]
+    pub fn remove(&mut self, other: Self) {
+        self.0 &= !other.0;
+    }
+}
+
+impl Default for AllowedOps {
+    #[inline]
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
+
+impl BitOr for AllowedOps {
+    type Output = Self;
+
+    #[inline]
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for AllowedOps {
+    #[inline]
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl From<OpKind> for AllowedOps {
+    fn from(op: OpKind) -> Self {
+        match op {
+            OpKind::Number | OpKind::Negate => Self::empty(),
+            OpKind::Add => Self::ADD,
+            OpKind::Subtract => Self::SUBTRACT,
+            OpKind::Multiply => Self::MULTIPLY,
+            OpKind::Divide => Self::DIVIDE,
+            OpKind::Power => Self::POWER,
+            OpKind::Sqrt => Self::SQRT,
+            OpKind::Factorial => Self(Self::FACTORIAL.0 | Self::FACTORIAL_DIVIDE.0),
+            OpKind::Average => Self::AVERAGE,
+            OpKind::Round => Self::ROUND,
+            OpKind::Subfactorial => Self::SUBFACTORIAL,
+            OpKind::NthPrime => Self::NTH_PRIME,
+            OpKind::PrimePi => Self::PRIME_PI,
+            OpKind::DoubleFactorial => Self::DOUBLE_FACTORIAL,
+            OpKind::Root => Self::ROOT,
+            OpKind::Decimal => Self::DECIMAL,
+            OpKind::RepeatingDecimal => Self::REPEATING_DECIMAL,
+            OpKind::Percent => Self::PERCENT,
+            OpKind::Log => Self::LOG,
+            OpKind::Tetration => Self::TETRATION,
+        }
+    }
+}
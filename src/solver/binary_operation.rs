@@ -1,10 +1,10 @@
-use super::{Solver, State};
+use super::{AllowedOps, Collector, RangeCheck, State};
 use crate::number_theory::factorial_divide;
 use crate::quadratic::PRIMES;
-use crate::{Expression, IntegralQuadratic, Number, RationalQuadratic};
+use crate::{Expression, GaussianRational, IntegralQuadratic, Number, RationalQuadratic};
 use num::rational::Rational64;
-use num::traits::{Inv, Pow};
-use num::One;
+use num::traits::{CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, Inv, Pow};
+use num::{checked_pow, One, Signed, Zero};
 
 trait Digits {
     fn digits(&self) -> f64;
@@ -17,6 +17,13 @@ impl Digits for i64 {
     }
 }
 
+impl Digits for i128 {
+    #[inline]
+    fn digits(&self) -> f64 {
+        (*self as f64).log2()
+    }
+}
+
 impl Digits for Rational64 {
     #[inline]
     fn digits(&self) -> f64 {
@@ -58,20 +65,55 @@ pub(super) trait BinaryOperation<T: Number> {
     fn divide(&mut self, x: &State<T>, y: &State<T>) -> bool;
     fn power(&mut self, x: &State<T>, y: &State<T>) -> bool;
     fn factorial_divide(&mut self, x: &State<T>, y: &State<T>) -> bool;
+    fn average(&mut self, x: &State<T>, y: &State<T>) -> bool;
 }
 
-impl<T: Number> BinaryOperation<T> for Solver<T> {
-    default fn binary_operation(&mut self, _x: State<T>, _y: State<T>) -> bool {
-        false
+impl<'a, T: Number> BinaryOperation<T> for Collector<'a, T> {
+    default fn binary_operation(&mut self, x: State<T>, y: State<T>) -> bool {
+        let mut found = false;
+        if self.divide(&x, &y) {
+            found = true;
+        }
+        if self.divide(&y, &x) {
+            found = true;
+        }
+        if self.multiply(&x, &y) {
+            found = true;
+        }
+        if self.add(&x, &y) {
+            found = true;
+        }
+        if self.subtract(&x, &y) {
+            found = true;
+        }
+        if self.power(&x, &y) {
+            found = true;
+        }
+        if self.power(&y, &x) {
+            found = true;
+        }
+        if x.number.is_int() && y.number.is_int() && self.factorial_divide(&x, &y) {
+            found = true;
+        }
+        if self.average(&x, &y) {
+            found = true;
+        }
+        found
     }
 
     default fn add(&mut self, x: &State<T>, y: &State<T>) -> bool {
+        if !self.allows(AllowedOps::ADD) {
+            return false;
+        }
         self.try_insert(x.number + y.number, x.digits + y.digits, || {
             Expression::from_add(x.expression.clone(), y.expression.clone())
         })
     }
 
     default fn subtract(&mut self, x: &State<T>, y: &State<T>) -> bool {
+        if !self.allows(AllowedOps::SUBTRACT) {
+            return false;
+        }
         let result = x.number - y.number;
         if result.is_zero() {
             false
@@ -87,6 +129,9 @@ impl<T: Number> BinaryOperation<T> for Solver<T> {
     }
 
     default fn multiply(&mut self, x: &State<T>, y: &State<T>) -> bool {
+        if !self.allows(AllowedOps::MULTIPLY) {
+            return false;
+        }
         self.try_insert(x.number * y.number, x.digits + y.digits, || {
             Expression::from_multiply(x.expression.clone(), y.expression.clone())
         })
@@ -101,6 +146,9 @@ impl<T: Number> BinaryOperation<T> for Solver<T> {
     }
 
     default fn factorial_divide(&mut self, x: &State<T>, y: &State<T>) -> bool {
+        if !self.allows(AllowedOps::FACTORIAL | AllowedOps::DIVIDE | AllowedOps::FACTORIAL_DIVIDE) {
+            return false;
+        }
         if x.number == y.number {
             return false;
         }
@@ -124,21 +172,56 @@ impl<T: Number> BinaryOperation<T> for Solver<T> {
         {
             return false;
         }
-        self.try_insert(
-            factorial_divide(x_int, y_int).into(),
-            x.digits + y.digits,
-            || {
+        if let Some(result) = factorial_divide(x_int, y_int) {
+            self.try_insert(result.into(), x.digits + y.digits, || {
                 Expression::from_divide(
                     Expression::from_factorial(x.expression.clone()),
                     Expression::from_factorial(y.expression.clone()),
                 )
-            },
-        )
+            })
+        } else {
+            false
+        }
+    }
+    default fn average(&mut self, x: &State<T>, y: &State<T>) -> bool {
+        if !self.allows(AllowedOps::AVERAGE) || x.number == y.number {
+            return false;
+        }
+        let sum = x.number + y.number;
+        let result = sum / T::from(2);
+        if result + result != sum {
+            return false;
+        }
+        self.try_insert(result, x.digits + y.digits, || {
+            Expression::from_average(x.expression.clone(), y.expression.clone())
+        })
     }
 }
 
-impl BinaryOperation<i64> for Solver<i64> {
-    fn binary_operation(&mut self, x: State<i64>, y: State<i64>) -> bool {
+impl<'a, T: Number + CheckedAdd + CheckedDiv> Collector<'a, T> {
+    fn checked_average(&mut self, x: &State<T>, y: &State<T>) -> bool {
+        if !self.allows(AllowedOps::AVERAGE) || x.number == y.number {
+            return false;
+        }
+        let sum = match x.number.checked_add(&y.number) {
+            Some(sum) => sum,
+            None => return false,
+        };
+        let result = match sum.checked_div(&T::from(2)) {
+            Some(result) => result,
+            None => return false,
+        };
+        if result.checked_add(&result) != Some(sum) {
+            return false;
+        }
+        self.try_insert(result, x.digits + y.digits, || {
+            Expression::from_average(x.expression.clone(), y.expression.clone())
+        })
+    }
+}
+
+impl<'a> BinaryOperation<i128> for Collector<'a, i128> {
+    fn binary_operation(&mut self, x: State<i128>, y: State<i128>) -> bool {
         let mut found = false;
         if x.number < y.number {
             if self.divide(&y, &x) {
@@ -162,25 +245,41 @@ impl BinaryOperation<i64> for Solver<i64> {
         if self.power(&y, &x) {
             found = true;
         }
+        if self.tetration(&x, &y) {
+            found = true;
+        }
+        if self.tetration(&y, &x) {
+            found = true;
+        }
         if self.factorial_divide(&x, &y) {
             found = true;
         }
+        if self.average(&x, &y) {
+            found = true;
+        }
         found
     }
 
-    fn multiply(&mut self, x: &State<i64>, y: &State<i64>) -> bool {
-        if let Some(z) = x.number.checked_mul(y.number) {
-            self.try_insert(z, x.digits + y.digits, || {
-                Expression::from_multiply(x.expression.clone(), y.expression.clone())
-            })
-        } else {
-            false
+    fn multiply(&mut self, x: &State<i128>, y: &State<i128>) -> bool {
+        if !self.allows(AllowedOps::MULTIPLY) {
+            return false;
+        }
+        match x.number.checked_mul(y.number) {
+            Some(z) if !self.misses_prime_signature(z, x.digits + y.digits) => {
+                self.try_insert(z, x.digits + y.digits, || {
+                    Expression::from_multiply(x.expression.clone(), y.expression.clone())
+                })
+            }
+            _ => false,
         }
     }
 
-    fn divide(&mut self, x: &State<i64>, y: &State<i64>) -> bool {
+    fn divide(&mut self, x: &State<i128>, y: &State<i128>) -> bool {
+        if !self.allows(AllowedOps::DIVIDE) {
+            return false;
+        }
         if x.number == y.number {
-            return if x.number == self.n {
+            return if x.number == self.n.into() {
                 self.try_insert(1, 2, || {
                     Expression::from_divide(x.expression.clone(), x.expression.clone())
                 })
@@ -188,7 +287,9 @@ impl BinaryOperation<i64> for Solver<i64> {
                 false
             };
         }
-        if x.number % y.number == 0 {
+        if x.number % y.number == 0
+            && !self.misses_prime_signature(x.number / y.number, x.digits + y.digits)
+        {
             self.try_insert(x.number / y.number, x.digits + y.digits, || {
                 Expression::from_divide(x.expression.clone(), y.expression.clone())
             })
@@ -197,7 +298,10 @@ impl BinaryOperation<i64> for Solver<i64> {
         }
     }
 
-    fn power(&mut self, x: &State<i64>, y: &State<i64>) -> bool {
+    fn power(&mut self, x: &State<i128>, y: &State<i128>) -> bool {
+        if !self.allows(AllowedOps::POWER) {
+            return false;
+        }
         if x.number == 1 || y.number == 1 {
             return false;
         }
@@ -215,16 +319,27 @@ impl BinaryOperation<i64> for Solver<i64> {
                 return false;
             }
         }
-        self.try_insert(x.number.pow(exponent), x.digits + y.digits, || {
-            Expression::from_sqrt(
-                Expression::from_power(x.expression.clone(), y.expression.clone()),
-                sqrt_order,
-            )
-        })
+        if sqrt_order > 0 && !self.allows(AllowedOps::SQRT) {
+            return false;
+        }
+        if let Some(result) = checked_pow(x.number, exponent as usize) {
+            self.try_insert(result, x.digits + y.digits, || {
+                Expression::from_sqrt(
+                    Expression::from_power(x.expression.clone(), y.expression.clone()),
+                    sqrt_order,
+                )
+            })
+        } else {
+            false
+        }
+    }
+    #[inline]
+    fn average(&mut self, x: &State<i128>, y: &State<i128>) -> bool {
+        self.checked_average(x, y)
     }
 }
 
-impl BinaryOperation<Rational64> for Solver<Rational64> {
+impl<'a> BinaryOperation<Rational64> for Collector<'a, Rational64> {
     fn binary_operation(&mut self, x: State<Rational64>, y: State<Rational64>) -> bool {
         let mut found = false;
         if self.divide(&x, &y) {
@@ -250,10 +365,60 @@ impl BinaryOperation<Rational64> for Solver<Rational64> {
         if x.number.is_integer() && y.number.is_integer() && self.factorial_divide(&x, &y) {
             found = true;
         }
+        if self.average(&x, &y) {
+            found = true;
+        }
         found
     }
 
+    fn add(&mut self, x: &State<Rational64>, y: &State<Rational64>) -> bool {
+        if !self.allows(AllowedOps::ADD) {
+            return false;
+        }
+        if let Some(result) = x.number.checked_add(&y.number) {
+            self.try_insert(result, x.digits + y.digits, || {
+                Expression::from_add(x.expression.clone(), y.expression.clone())
+            })
+        } else {
+            false
+        }
+    }
+
+    fn subtract(&mut self, x: &State<Rational64>, y: &State<Rational64>) -> bool {
+        if !self.allows(AllowedOps::SUBTRACT) {
+            return false;
+        }
+        match x.number.checked_sub(&y.number) {
+            Some(result) if result.is_zero() => false,
+            Some(result) if result.is_negative() => {
+                self.try_insert(-result, x.digits + y.digits, || {
+                    Expression::from_subtract(y.expression.clone(), x.expression.clone())
+                })
+            }
+            Some(result) => self.try_insert(result, x.digits + y.digits, || {
+                Expression::from_subtract(x.expression.clone(), y.expression.clone())
+            }),
+            None => false,
+        }
+    }
+
+    fn multiply(&mut self, x: &State<Rational64>, y: &State<Rational64>) -> bool {
+        if !self.allows(AllowedOps::MULTIPLY) {
+            return false;
+        }
+        if let Some(result) = x.number.checked_mul(&y.number) {
+            self.try_insert(result, x.digits + y.digits, || {
+                Expression::from_multiply(x.expression.clone(), y.expression.clone())
+            })
+        } else {
+            false
+        }
+    }
+
     fn divide(&mut self, x: &State<Rational64>, y: &State<Rational64>) -> bool {
+        if !self.allows(AllowedOps::DIVIDE) {
+            return false;
+        }
         if x.number == y.number {
             return if x.number.to_int() == Some(self.n) {
                 self.try_insert(Rational64::one(), 2, || {
@@ -264,7 +429,10 @@ impl BinaryOperation<Rational64> for Solver<Rational64> {
             };
         }
         let mut found = false;
-        let result = x.number / y.number;
+        let result = match x.number.checked_div(&y.number) {
+            Some(result) => result,
+            None => return false,
+        };
         if !y.expression.is_divide() {
             if self.try_insert(result, x.digits + y.digits, || {
                 Expression::from_divide(x.expression.clone(), y.expression.clone())
@@ -283,6 +451,9 @@ impl BinaryOperation<Rational64> for Solver<Rational64> {
     }
 
     fn power(&mut self, x: &State<Rational64>, y: &State<Rational64>) -> bool {
+        if !self.allows(AllowedOps::POWER) {
+            return false;
+        }
         if x.number.is_one() || y.number.is_one() || *y.number.numer() > 0x40000000 {
             return false;
         }
@@ -297,8 +468,14 @@ impl BinaryOperation<Rational64> for Solver<Rational64> {
                 return false;
             }
         }
+        if sqrt_order > 0 && !self.allows(AllowedOps::SQRT) {
+            return false;
+        }
         let mut found = false;
-        let z = x.number.pow(exponent);
+        let z = match checked_pow(x.number, exponent as usize) {
+            Some(z) => z,
+            None => return false,
+        };
         if self.try_insert(z, x.digits + y.digits, || {
             Expression::from_sqrt(
                 Expression::from_power(x.expression.clone(), y.expression.clone()),
@@ -324,6 +501,9 @@ impl BinaryOperation<Rational64> for Solver<Rational64> {
     }
 
     fn factorial_divide(&mut self, x: &State<Rational64>, y: &State<Rational64>) -> bool {
+        if !self.allows(AllowedOps::FACTORIAL | AllowedOps::DIVIDE | AllowedOps::FACTORIAL_DIVIDE) {
+            return false;
+        }
         if x.number == y.number {
             return false;
         }
@@ -350,7 +530,10 @@ impl BinaryOperation<Rational64> for Solver<Rational64> {
         let mut found = false;
         let x_expression = Expression::from_factorial(x.expression.clone());
         let y_expression = Expression::from_factorial(y.expression.clone());
-        let result = factorial_divide(x_int, y_int).into();
+        let result = match factorial_divide(x_int, y_int) {
+            Some(result) => result.into(),
+            None => return false,
+        };
         if self.try_insert(result, x.digits + y.digits, || {
             Expression::from_divide(x_expression.clone(), y_expression.clone())
         }) {
@@ -363,9 +546,13 @@ impl BinaryOperation<Rational64> for Solver<Rational64> {
         }
         found
     }
+    #[inline]
+    fn average(&mut self, x: &State<Rational64>, y: &State<Rational64>) -> bool {
+        self.checked_average(x, y)
+    }
 }
 
-impl BinaryOperation<IntegralQuadratic> for Solver<IntegralQuadratic> {
+impl<'a> BinaryOperation<IntegralQuadratic> for Collector<'a, IntegralQuadratic> {
     fn binary_operation(
         &mut self,
         x: State<IntegralQuadratic>,
@@ -392,6 +579,9 @@ impl BinaryOperation<IntegralQuadratic> for Solver<IntegralQuadratic> {
                 if self.subtract(&x, &y) {
                     found = true;
                 }
+                if self.average(&x, &y) {
+                    found = true;
+                }
             }
         }
         if y.number.is_int() && (!self.progressive || !x.number.is_int()) && self.power(&x, &y) {
@@ -411,6 +601,9 @@ impl BinaryOperation<IntegralQuadratic> for Solver<IntegralQuadratic> {
     }
 
     fn divide(&mut self, x: &State<IntegralQuadratic>, y: &State<IntegralQuadratic>) -> bool {
+        if !self.allows(AllowedOps::DIVIDE) {
+            return false;
+        }
         if x.number == y.number {
             return if x.number.to_int() == Some(self.n) {
                 self.try_insert(IntegralQuadratic::one(), 2, || {
@@ -430,6 +623,9 @@ impl BinaryOperation<IntegralQuadratic> for Solver<IntegralQuadratic> {
     }
 
     fn power(&mut self, x: &State<IntegralQuadratic>, y: &State<IntegralQuadratic>) -> bool {
+        if !self.allows(AllowedOps::POWER) {
+            return false;
+        }
         if x.number.is_one() || y.number.is_one() {
             return false;
         }
@@ -448,16 +644,23 @@ impl BinaryOperation<IntegralQuadratic> for Solver<IntegralQuadratic> {
                 return false;
             }
         }
-        self.try_insert(x.number.pow(exponent), x.digits + y.digits, || {
-            Expression::from_sqrt(
-                Expression::from_power(x.expression.clone(), y.expression.clone()),
-                sqrt_order,
-            )
-        })
+        if sqrt_order > 0 && !self.allows(AllowedOps::SQRT) {
+            return false;
+        }
+        if let Some(result) = x.number.checked_pow(exponent) {
+            self.try_insert(result, x.digits + y.digits, || {
+                Expression::from_sqrt(
+                    Expression::from_power(x.expression.clone(), y.expression.clone()),
+                    sqrt_order,
+                )
+            })
+        } else {
+            false
+        }
     }
 }
 
-impl BinaryOperation<RationalQuadratic> for Solver<RationalQuadratic> {
+impl<'a> BinaryOperation<RationalQuadratic> for Collector<'a, RationalQuadratic> {
     fn binary_operation(
         &mut self,
         x: State<RationalQuadratic>,
@@ -480,6 +683,9 @@ impl BinaryOperation<RationalQuadratic> for Solver<RationalQuadratic> {
                 if self.subtract(&x, &y) {
                     found = true;
                 }
+                if self.average(&x, &y) {
+                    found = true;
+                }
             }
         }
         if y.number.is_int() && (!self.progressive || !x.number.is_rational()) && self.power(&x, &y)
@@ -490,6 +696,12 @@ impl BinaryOperation<RationalQuadratic> for Solver<RationalQuadratic> {
         {
             found = true;
         }
+        if y.number.is_rational() && !y.number.is_int() && self.rational_power(&x, &y) {
+            found = true;
+        }
+        if x.number.is_rational() && !x.number.is_int() && self.rational_power(&y, &x) {
+            found = true;
+        }
         if x.number.is_int()
             && y.number.is_int()
             && !self.progressive
@@ -500,7 +712,54 @@ impl BinaryOperation<RationalQuadratic> for Solver<RationalQuadratic> {
         found
     }
 
+    fn add(&mut self, x: &State<RationalQuadratic>, y: &State<RationalQuadratic>) -> bool {
+        if !self.allows(AllowedOps::ADD) {
+            return false;
+        }
+        if let Some(result) = x.number.checked_add(&y.number) {
+            self.try_insert(result, x.digits + y.digits, || {
+                Expression::from_add(x.expression.clone(), y.expression.clone())
+            })
+        } else {
+            false
+        }
+    }
+
+    fn subtract(&mut self, x: &State<RationalQuadratic>, y: &State<RationalQuadratic>) -> bool {
+        if !self.allows(AllowedOps::SUBTRACT) {
+            return false;
+        }
+        match x.number.checked_sub(&y.number) {
+            Some(result) if result.is_zero() => false,
+            Some(result) if result.is_negative() => {
+                self.try_insert(-result, x.digits + y.digits, || {
+                    Expression::from_subtract(y.expression.clone(), x.expression.clone())
+                })
+            }
+            Some(result) => self.try_insert(result, x.digits + y.digits, || {
+                Expression::from_subtract(x.expression.clone(), y.expression.clone())
+            }),
+            None => false,
+        }
+    }
+
+    fn multiply(&mut self, x: &State<RationalQuadratic>, y: &State<RationalQuadratic>) -> bool {
+        if !self.allows(AllowedOps::MULTIPLY) {
+            return false;
+        }
+        if let Some(result) = x.number.checked_mul(&y.number) {
+            self.try_insert(result, x.digits + y.digits, || {
+                Expression::from_multiply(x.expression.clone(), y.expression.clone())
+            })
+        } else {
+            false
+        }
+    }
+
     fn divide(&mut self, x: &State<RationalQuadratic>, y: &State<RationalQuadratic>) -> bool {
+        if !self.allows(AllowedOps::DIVIDE) {
+            return false;
+        }
         if x.number == y.number {
             return if x.number.to_int() == Some(self.n) {
                 self.try_insert(RationalQuadratic::one(), 2, || {
@@ -511,7 +770,10 @@ impl BinaryOperation<RationalQuadratic> for Solver<RationalQuadratic> {
             };
         }
         let mut found = false;
-        let result = x.number / y.number;
+        let result = match x.number.checked_div(&y.number) {
+            Some(result) => result,
+            None => return false,
+        };
         if !y.expression.is_divide() {
             if self.try_insert(result, x.digits + y.digits, || {
                 Expression::from_divide(x.expression.clone(), y.expression.clone())
@@ -530,6 +792,9 @@ impl BinaryOperation<RationalQuadratic> for Solver<RationalQuadratic> {
     }
 
     fn power(&mut self, x: &State<RationalQuadratic>, y: &State<RationalQuadratic>) -> bool {
+        if !self.allows(AllowedOps::POWER) {
+            return false;
+        }
         if x.number.is_one() || y.number.is_one() {
             return false;
         }
@@ -548,7 +813,13 @@ impl BinaryOperation<RationalQuadratic> for Solver<RationalQuadratic> {
                 return false;
             }
         }
-        let result = x.number.pow(exponent);
+        if sqrt_order > 0 && !self.allows(AllowedOps::SQRT) {
+            return false;
+        }
+        let result = match x.number.checked_pow(exponent) {
+            Some(result) => result,
+            None => return false,
+        };
         if self.try_insert(result, x.digits + y.digits, || {
             Expression::from_sqrt(
                 Expression::from_power(x.expression.clone(), y.expression.clone()),
@@ -576,6 +847,9 @@ impl BinaryOperation<RationalQuadratic> for Solver<RationalQuadratic> {
         x: &State<RationalQuadratic>,
         y: &State<RationalQuadratic>,
     ) -> bool {
+        if !self.allows(AllowedOps::FACTORIAL | AllowedOps::DIVIDE | AllowedOps::FACTORIAL_DIVIDE) {
+            return false;
+        }
         if x.number == y.number {
             return false;
         }
@@ -602,7 +876,10 @@ impl BinaryOperation<RationalQuadratic> for Solver<RationalQuadratic> {
         let mut found = false;
         let x_expression = Expression::from_factorial(x.expression.clone());
         let y_expression = Expression::from_factorial(y.expression.clone());
-        let result = factorial_divide(x_int, y_int).into();
+        let result = match factorial_divide(x_int, y_int) {
+            Some(result) => result.into(),
+            None => return false,
+        };
         if self.try_insert(result, x.digits + y.digits, || {
             Expression::from_divide(x_expression.clone(), y_expression.clone())
         }) {
@@ -615,4 +892,341 @@ impl BinaryOperation<RationalQuadratic> for Solver<RationalQuadratic> {
         }
         found
     }
+    #[inline]
+    fn average(&mut self, x: &State<RationalQuadratic>, y: &State<RationalQuadratic>) -> bool {
+        self.checked_average(x, y)
+    }
+}
+
+impl<'a> BinaryOperation<GaussianRational> for Collector<'a, GaussianRational> {
+    fn binary_operation(&mut self, x: State<GaussianRational>, y: State<GaussianRational>) -> bool {
+        if self.progressive
+            && x.number.is_real()
+            && y.number.is_real()
+            && !x.number.is_negative()
+            && !y.number.is_negative()
+        {
+            return self.subtract(&x, &y);
+        }
+        let mut found = false;
+        if self.divide(&x, &y) {
+            found = true;
+        }
+        if self.multiply(&x, &y) {
+            found = true;
+        }
+        if self.add(&x, &y) {
+            found = true;
+        }
+        if self.subtract(&x, &y) {
+            found = true;
+        }
+        if self.average(&x, &y) {
+            found = true;
+        }
+        if y.number.is_int() && self.power(&x, &y) {
+            found = true;
+        }
+        if x.number.is_int() && self.power(&y, &x) {
+            found = true;
+        }
+        found
+    }
+
+    fn add(&mut self, x: &State<GaussianRational>, y: &State<GaussianRational>) -> bool {
+        if !self.allows(AllowedOps::ADD) {
+            return false;
+        }
+        if let Some(result) = x.number.checked_add(&y.number) {
+            self.try_insert(result, x.digits + y.digits, || {
+                Expression::from_add(x.expression.clone(), y.expression.clone())
+            })
+        } else {
+            false
+        }
+    }
+
+    fn subtract(&mut self, x: &State<GaussianRational>, y: &State<GaussianRational>) -> bool {
+        if !self.allows(AllowedOps::SUBTRACT) || x.number == y.number {
+            return false;
+        }
+        let result = match x.number.checked_sub(&y.number) {
+            Some(result) => result,
+            None => return false,
+        };
+        let mut found = false;
+        if self.try_insert(result, x.digits + y.digits, || {
+            Expression::from_subtract(x.expression.clone(), y.expression.clone())
+        }) {
+            found = true;
+        }
+        if self.try_insert(-result, x.digits + y.digits, || {
+            Expression::from_subtract(y.expression.clone(), x.expression.clone())
+        }) {
+            found = true;
+        }
+        found
+    }
+
+    fn multiply(&mut self, x: &State<GaussianRational>, y: &State<GaussianRational>) -> bool {
+        if !self.allows(AllowedOps::MULTIPLY) {
+            return false;
+        }
+        if let Some(result) = x.number.checked_mul(&y.number) {
+            self.try_insert(result, x.digits + y.digits, || {
+                Expression::from_multiply(x.expression.clone(), y.expression.clone())
+            })
+        } else {
+            false
+        }
+    }
+
+    fn divide(&mut self, x: &State<GaussianRational>, y: &State<GaussianRational>) -> bool {
+        if !self.allows(AllowedOps::DIVIDE) {
+            return false;
+        }
+        if x.number == y.number {
+            return if x.number.to_int() == Some(self.n) {
+                self.try_insert(GaussianRational::one(), 2, || {
+                    Expression::from_divide(x.expression.clone(), x.expression.clone())
+                })
+            } else {
+                false
+            };
+        }
+        let mut found = false;
+        if let Some(result) = x.number.checked_div(&y.number) {
+            if self.try_insert(result, x.digits + y.digits, || {
+                Expression::from_divide(x.expression.clone(), y.expression.clone())
+            }) {
+                found = true;
+            }
+        }
+        if let Some(result) = y.number.checked_div(&x.number) {
+            if self.try_insert(result, x.digits + y.digits, || {
+                Expression::from_divide(y.expression.clone(), x.expression.clone())
+            }) {
+                found = true;
+            }
+        }
+        found
+    }
+
+    fn power(&mut self, x: &State<GaussianRational>, y: &State<GaussianRational>) -> bool {
+        if !self.allows(AllowedOps::POWER) {
+            return false;
+        }
+        if x.number.is_zero() || x.number.is_one() || y.number.is_one() {
+            return false;
+        }
+        let y_int = y.number.to_int().unwrap();
+        if y_int < 2 || y_int > self.limits.max_digits as i64 * 2 {
+            return false;
+        }
+        let mut result = GaussianRational::one();
+        for _ in 0..y_int {
+            result = match result.checked_mul(&x.number) {
+                Some(result) if self.range_check(&result) => result,
+                _ => return false,
+            };
+        }
+        if self.try_insert(result, x.digits + y.digits, || {
+            Expression::from_power(x.expression.clone(), y.expression.clone())
+        }) {
+            true
+        } else if !x.expression.is_divide() {
+            if let Some(inverse) = GaussianRational::one().checked_div(&result) {
+                self.try_insert(inverse, x.digits + y.digits, || {
+                    Expression::from_power(
+                        x.expression.clone(),
+                        Expression::from_negate(y.expression.clone()),
+                    )
+                })
+            } else {
+                false
+            }
+        } else {
+            false
+        }
+    }
+
+    #[inline]
+    fn average(&mut self, x: &State<GaussianRational>, y: &State<GaussianRational>) -> bool {
+        self.checked_average(x, y)
+    }
+}
+
+impl<'a> Collector<'a, RationalQuadratic> {
+    fn rational_power(
+        &mut self,
+        x: &State<RationalQuadratic>,
+        y: &State<RationalQuadratic>,
+    ) -> bool {
+        if !self.allows(AllowedOps::POWER | AllowedOps::RATIONAL_POWER) || x.number.is_one() {
+            return false;
+        }
+        let exponent = y.number.rational_part();
+        if *exponent.numer() > 0x40000000
+            || *exponent.denom() > 1 << self.limits.max_quadratic_power.max(1)
+            || x.number.digits() * *exponent.numer() as f64
+                > self.limits.max_digits as f64 * *exponent.denom() as f64
+        {
+            return false;
+        }
+        match x.number.pow_rational(exponent) {
+            Some(result) => self.try_insert(result, x.digits + y.digits, || {
+                Expression::from_power(x.expression.clone(), y.expression.clone())
+            }),
+            None => false,
+        }
+    }
+}
+
+impl<'a> Collector<'a, i128> {
+    fn misses_prime_signature(&self, x: i128, digits: usize) -> bool {
+        match &self.prime_signature {
+            Some((depth, primes)) if *depth == digits => {
+                let mut x = x.abs();
+                if x == 0 {
+                    return true;
+                }
+                for p in primes {
+                    while x % p == 0 {
+                        x /= p;
+                    }
+                }
+                x != 1
+            }
+            _ => false,
+        }
+    }
+
+    fn tetration(&mut self, x: &State<i128>, y: &State<i128>) -> bool {
+        if !self.allows(AllowedOps::TETRATION)
+            || x.number < 2
+            || y.number < 2
+            || y.number > self.limits.max_tetration_height.min(3) as i128
+        {
+            return false;
+        }
+        let x_digits = x.number.digits();
+        let mut result = 1i128;
+        for _ in 0..y.number {
+            if x_digits * result as f64 > self.limits.max_digits as f64 {
+                return false;
+            }
+            result = match checked_pow(x.number, result as usize) {
+                Some(result) => result,
+                None => return false,
+            };
+        }
+        self.try_insert(result, x.digits + y.digits, || {
+            Expression::from_tetration(x.expression.clone(), y.expression.clone())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::{Limits, Solver};
+
+    fn state<T: Number>(number: T) -> State<T> {
+        State {
+            number,
+            digits: 1,
+            expression: Expression::from_number(1),
+        }
+    }
+
+    fn averages<T: Number + CheckedAdd + CheckedDiv>(x: T, y: T) -> Vec<T> {
+        let solver = Solver::<T>::new(
+            1,
+            Limits {
+                allowed_ops: AllowedOps::STANDARD | AllowedOps::AVERAGE,
+                max_digits: 16,
+                ..Limits::default()
+            },
+        );
+        let mut collector = Collector::new(&solver, vec![]);
+        collector.average(&state(x), &state(y));
+        let (candidates, _) = collector.into_candidates();
+        candidates
+            .into_iter()
+            .map(|(number, _, _)| number)
+            .collect()
+    }
+
+    #[test]
+    fn averages_integers_with_even_sums_only() {
+        assert_eq!(averages(3i128, 5), [4]);
+        assert!(averages(3i128, 4).is_empty());
+    }
+
+    #[test]
+    fn average_overflow_is_rejected() {
+        assert!(averages(i128::MAX, i128::MAX - 2).is_empty());
+        assert!(averages(Rational64::from(i64::MAX), Rational64::from(i64::MAX - 2)).is_empty());
+    }
+
+    fn tetrations(x: i128, y: i128, max_digits: usize) -> Vec<i128> {
+        let solver = Solver::<i128>::new(
+            1,
+            Limits {
+                allowed_ops: AllowedOps::STANDARD | AllowedOps::TETRATION,
+                max_digits,
+                max_tetration_height: 3,
+                ..Limits::default()
+            },
+        );
+        let mut collector = Collector::new(&solver, vec![]);
+        collector.tetration(&state(x), &state(y));
+        let (candidates, _) = collector.into_candidates();
+        candidates
+            .into_iter()
+            .map(|(number, _, _)| number)
+            .collect()
+    }
+
+    #[test]
+    fn tetration_is_guarded() {
+        assert_eq!(tetrations(2, 3, 32), [16]);
+        assert_eq!(tetrations(2, 2, 32), [4]);
+        assert!(tetrations(3, 3, 32).is_empty());
+        assert_eq!(tetrations(3, 3, 64), [7625597484987]);
+        assert!(tetrations(4, 3, 126).is_empty());
+        assert!(tetrations(2, 4, 126).is_empty());
+    }
+
+    #[test]
+    fn prime_signature_pruning_keeps_minimal_digits() {
+        let limits = |prune_by_prime_signature| Limits {
+            max_digits: 32,
+            max_factorial: 10,
+            prune_by_prime_signature,
+            ..Limits::default()
+        };
+        for target in [96, 250, 625, 1000, 1024, 1999, 4096] {
+            for max_depth in 3..=5 {
+                let mut plain = Solver::<i128>::new(5, limits(false));
+                let mut pruned = Solver::<i128>::new(5, limits(true));
+                let expected = plain
+                    .solve(target, Some(max_depth))
+                    .map(|(_, digits)| digits);
+                assert_eq!(
+                    pruned
+                        .solve(target, Some(max_depth))
+                        .map(|(_, digits)| digits),
+                    expected,
+                    "{} within {} digits",
+                    target,
+                    max_depth
+                );
+                assert!(pruned.stats().candidates <= plain.stats().candidates);
+                if expected.is_none() {
+                    assert!(pruned.stats().candidates < plain.stats().candidates);
+                }
+            }
+        }
+    }
 }
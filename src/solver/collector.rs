@@ -0,0 +1,80 @@
+use super::{RangeCheck, Solver};
+use crate::{Expression, Number, Rc};
+use std::ops::Deref;
+
+pub(super) type Candidate<T> = (T, usize, Rc<Expression>);
+
+#[derive(Clone, Copy, Debug, Default)]
+pub(super) struct FilterCounts {
+    pub(super) out_of_range: usize,
+    pub(super) already_searched: usize,
+}
+
+pub(super) struct Collector<'a, T: Number> {
+    solver: &'a Solver<T>,
+    candidates: Vec<Candidate<T>>,
+    target: Option<T>,
+    filtered: FilterCounts,
+}
+
+impl<'a, T: Number> Collector<'a, T> {
+    #[inline]
+    pub(super) fn new(solver: &'a Solver<T>, candidates: Vec<Candidate<T>>) -> Self {
+        Self {
+            solver,
+            candidates,
+            target: None,
+            filtered: FilterCounts::default(),
+        }
+    }
+
+    #[inline]
+    pub(super) fn for_target(solver: &'a Solver<T>, target: T) -> Self {
+        Self {
+            solver,
+            candidates: vec![],
+            target: Some(target),
+            filtered: FilterCounts::default(),
+        }
+    }
+
+    pub(super) fn try_insert(
+        &mut self,
+        x: T,
+        digits: usize,
+        expression_fn: impl FnOnce() -> Rc<Expression>,
+    ) -> bool {
+        if let Some(target) = self.target {
+            if x == target {
+                self.candidates.push((x, digits, expression_fn()));
+            }
+            return false;
+        }
+        if !self.solver.range_check(&x) {
+            self.filtered.out_of_range += 1;
+            return false;
+        }
+        if let Some((_, x_digits)) = self.solver.states.get(&x) {
+            if !self.solver.replaces_equal_depth() || *x_digits != digits {
+                self.filtered.already_searched += 1;
+                return false;
+            }
+        }
+        self.candidates.push((x, digits, expression_fn()));
+        x == self.solver.target
+    }
+
+    #[inline]
+    pub(super) fn into_candidates(self) -> (Vec<Candidate<T>>, FilterCounts) {
+        (self.candidates, self.filtered)
+    }
+}
+
+impl<'a, T: Number> Deref for Collector<'a, T> {
+    type Target = Solver<T>;
+
+    #[inline]
+    fn deref(&self) -> &Solver<T> {
+        self.solver
+    }
+}
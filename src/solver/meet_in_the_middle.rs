@@ -0,0 +1,61 @@
+use super::{AllowedOps, Solver};
+use crate::{Expression, Number, Rc};
+
+pub(super) trait MeetInTheMiddle<T: Number> {
+    fn meet_in_the_middle(&self, digits: usize) -> Option<Rc<Expression>>;
+}
+
+impl<T: Number> MeetInTheMiddle<T> for Solver<T> {
+    default fn meet_in_the_middle(&self, _digits: usize) -> Option<Rc<Expression>> {
+        None
+    }
+}
+
+impl MeetInTheMiddle<i128> for Solver<i128> {
+    fn meet_in_the_middle(&self, digits: usize) -> Option<Rc<Expression>> {
+        let target = self.target;
+        for d1 in 1..=digits >> 1 {
+            let d2 = digits - d1;
+            for (x, e1) in self.reachable_at_depth(d1) {
+                let x = *x;
+                let complement = |y: Option<i128>| {
+                    let y = y.filter(|y| *y > 0)?;
+                    Some((y, self.expression_at(&y, d2)?))
+                };
+                if self.allows(AllowedOps::ADD) {
+                    if let Some((_, e2)) = complement(target.checked_sub(x)) {
+                        return Some(Expression::from_add(e1.clone(), e2));
+                    }
+                }
+                if self.allows(AllowedOps::SUBTRACT) {
+                    if let Some((_, e2)) = complement(x.checked_sub(target)) {
+                        return Some(Expression::from_subtract(e1.clone(), e2));
+                    }
+                    if let Some((_, e2)) = complement(x.checked_add(target)) {
+                        return Some(Expression::from_subtract(e2, e1.clone()));
+                    }
+                }
+                if self.allows(AllowedOps::MULTIPLY) && x != 0 && target % x == 0 {
+                    if let Some((_, e2)) = complement(Some(target / x)) {
+                        return Some(Expression::from_multiply(e1.clone(), e2));
+                    }
+                }
+                if self.allows(AllowedOps::DIVIDE) {
+                    if target != 0 && x % target == 0 {
+                        if let Some((y, e2)) = complement(Some(x / target)) {
+                            if y != x {
+                                return Some(Expression::from_divide(e1.clone(), e2));
+                            }
+                        }
+                    }
+                    if let Some((y, e2)) = complement(x.checked_mul(target)) {
+                        if y != x {
+                            return Some(Expression::from_divide(e2, e1.clone()));
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+}
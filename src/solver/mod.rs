@@ -1,23 +1,114 @@
-use crate::{Expression, Number};
-use std::collections::HashMap;
-use std::rc::Rc;
+use crate::progress_observer::SharedObserver;
+use crate::{Expression, Number, RationalQuadratic, Rc};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+#[cfg(feature = "search-timing")]
+use std::time::Duration;
 
+mod allowed_ops;
 mod binary_operation;
+mod collector;
+mod meet_in_the_middle;
+#[cfg(feature = "parallel")]
+mod parallel;
 mod range_check;
 mod searcher;
 mod solver;
+#[cfg(feature = "sqlite")]
+mod sqlite;
 mod unary_operation;
 
+pub use allowed_ops::AllowedOps;
 use binary_operation::BinaryOperation;
+use collector::{Candidate, Collector, FilterCounts};
+use meet_in_the_middle::MeetInTheMiddle;
+#[cfg(feature = "parallel")]
+pub(crate) use parallel::build_thread_pool;
 use range_check::RangeCheck;
 use searcher::Searcher;
 use unary_operation::UnaryOperation;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct Limits {
     pub max_digits: usize,
     pub max_factorial: i64,
+    pub max_subfactorial: i64,
+    pub max_double_factorial: i64,
+    pub max_prime: i64,
+    pub max_root_degree: u32,
+    pub max_tetration_height: i64,
+    pub max_percent_depth: usize,
     pub max_quadratic_power: u8,
+    pub free_negation: bool,
+    pub allowed_ops: AllowedOps,
+    pub max_distinct_operators: Option<usize>,
+    pub max_height: Option<usize>,
+    pub max_magnitude: Option<i128>,
+    pub max_factorials_per_expression: Option<usize>,
+    pub max_sqrts_per_expression: Option<usize>,
+    pub max_sqrt_nesting: Option<usize>,
+    pub allowed_values: Option<HashSet<i64>>,
+    pub prefer_small_intermediates: bool,
+    pub prefer_fewer_operators: bool,
+    pub meet_in_the_middle: bool,
+    pub prune_by_prime_signature: bool,
+    pub max_cache_entries: Option<usize>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_digits: 0,
+            max_factorial: 0,
+            max_subfactorial: 0,
+            max_double_factorial: 0,
+            max_prime: 0,
+            max_root_degree: 0,
+            max_tetration_height: 0,
+            max_percent_depth: 2,
+            max_quadratic_power: 0,
+            free_negation: false,
+            allowed_ops: AllowedOps::default(),
+            max_distinct_operators: None,
+            max_height: None,
+            max_magnitude: None,
+            max_factorials_per_expression: None,
+            max_sqrts_per_expression: None,
+            max_sqrt_nesting: None,
+            allowed_values: None,
+            prefer_small_intermediates: false,
+            prefer_fewer_operators: false,
+            meet_in_the_middle: false,
+            prune_by_prime_signature: false,
+            max_cache_entries: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Inconsistency {
+    pub number: Option<RationalQuadratic>,
+    pub digits: usize,
+    pub expression: Rc<Expression>,
+    pub evaluated: Option<RationalQuadratic>,
+    pub evaluated_digits: usize,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct SearchStats {
+    pub candidates: usize,
+    pub inserted: usize,
+    pub out_of_range: usize,
+    pub already_searched: usize,
+    pub skipped_follow_ups: usize,
+    pub table_size: usize,
+    #[cfg(feature = "search-timing")]
+    pub depth_times: Vec<Duration>,
 }
 
 struct State<T: Number> {
@@ -27,6 +118,7 @@ struct State<T: Number> {
 }
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 enum SearchState {
     None,
     Concat,
@@ -37,11 +129,26 @@ enum SearchState {
     Finish,
 }
 
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct SolverState<T: Number> {
+    target: T,
+    states: Vec<(T, Rc<Expression>, usize)>,
+    insertion_order: VecDeque<T>,
+    states_by_depth: Vec<Vec<T>>,
+    extra_states_by_depth: Vec<Vec<(T, Rc<Expression>)>>,
+    depth_searched: usize,
+    search_state: SearchState,
+    new_numbers: Vec<T>,
+    peak_size: usize,
+}
+
 #[derive(Clone)]
 pub struct Solver<T: Number> {
     n: i64,
     target: T,
     states: HashMap<T, (Rc<Expression>, usize)>,
+    insertion_order: VecDeque<T>,
     states_by_depth: Vec<Vec<T>>,
     extra_states_by_depth: Vec<Vec<(T, Rc<Expression>)>>,
     depth_searched: usize,
@@ -49,4 +156,13 @@ pub struct Solver<T: Number> {
     limits: Limits,
     progressive: bool,
     new_numbers: Vec<T>,
+    peak_size: usize,
+    stats: SearchStats,
+    primes: Vec<i64>,
+    cancel_flag: Option<Arc<AtomicBool>>,
+    observer: Option<SharedObserver>,
+    captured_solutions: Option<(T, Vec<Rc<Expression>>)>,
+    prime_signature: Option<(usize, Vec<i128>)>,
+    #[cfg(feature = "parallel")]
+    thread_pool: Option<Arc<rayon::ThreadPool>>,
 }
@@ -0,0 +1,144 @@
+use super::{BinaryOperation, Candidate, Collector, FilterCounts, SearchState, Solver, State};
+use crate::Number;
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use std::ops::Range;
+use std::sync::Arc;
+
+const ROWS_PER_CHUNK: usize = 64;
+
+impl<T: Number> Solver<T> {
+    pub fn set_threads(&mut self, threads: usize) {
+        self.set_thread_pool(build_thread_pool(threads));
+    }
+
+    #[inline]
+    pub(crate) fn set_thread_pool(&mut self, thread_pool: Option<Arc<ThreadPool>>) {
+        self.thread_pool = thread_pool;
+    }
+
+    pub(super) fn search_different_depth_parallel(
+        &mut self,
+        digits: usize,
+        start_depth: usize,
+        start_position: (usize, usize),
+    ) -> bool {
+        for d1 in start_depth..((digits + 1) >> 1) {
+            let d2 = digits - d1;
+            let l1 = self.states_by_depth[d1].len();
+            let (mut i, mut start_column) = if d1 == start_depth {
+                start_position
+            } else {
+                (0, 0)
+            };
+            while i < l1 {
+                if self.is_cancelled() {
+                    return false;
+                }
+                let end = usize::min(i + ROWS_PER_CHUNK, l1);
+                let (mut candidates, filtered) =
+                    self.collect_rows(d1, d2, i..end, start_column, false);
+                self.record_filtered(filtered);
+                self.search_state = SearchState::BinaryOperationOfDifferentDepth(d1, (end, 0));
+                if self.merge_candidates(&mut candidates) {
+                    return true;
+                }
+                i = end;
+                start_column = 0;
+            }
+            self.search_state = SearchState::BinaryOperationOfDifferentDepth(d1 + 1, (0, 0));
+        }
+        false
+    }
+
+    pub(super) fn search_same_depth_parallel(
+        &mut self,
+        d: usize,
+        start_position: (usize, usize),
+    ) -> bool {
+        let l = self.states_by_depth[d].len();
+        let (mut i, mut start_column) = start_position;
+        while i < l {
+            if self.is_cancelled() {
+                return false;
+            }
+            let end = usize::min(i + ROWS_PER_CHUNK, l);
+            let (mut candidates, filtered) = self.collect_rows(d, d, i..end, start_column, true);
+            self.record_filtered(filtered);
+            self.search_state = SearchState::BinaryOperationOfSameDepth((end, end));
+            if self.merge_candidates(&mut candidates) {
+                return true;
+            }
+            i = end;
+            start_column = 0;
+        }
+        false
+    }
+
+    fn collect_rows(
+        &self,
+        d1: usize,
+        d2: usize,
+        rows: Range<usize>,
+        start_column: usize,
+        same_depth: bool,
+    ) -> (Vec<Candidate<T>>, FilterCounts) {
+        let first_row = rows.start;
+        let thread_pool = self.thread_pool.as_ref().unwrap();
+        let chunks: Vec<(Vec<Candidate<T>>, FilterCounts)> = thread_pool.install(|| {
+            rows.into_par_iter()
+                .map(|i| {
+                    let n1 = self.states_by_depth[d1][i];
+                    let e1 = match self.expression_at(&n1, d1) {
+                        Some(expression) => expression,
+                        None => return (vec![], FilterCounts::default()),
+                    };
+                    let mut start = if same_depth { i } else { 0 };
+                    if i == first_row {
+                        start = usize::max(start, start_column);
+                    }
+                    let mut collector = Collector::new(self, vec![]);
+                    for n2 in self.states_by_depth[d2][start..].iter() {
+                        let e2 = match self.expression_at(n2, d2) {
+                            Some(expression) => expression,
+                            None => continue,
+                        };
+                        collector.binary_operation(
+                            State {
+                                number: n1,
+                                digits: d1,
+                                expression: e1.clone(),
+                            },
+                            State {
+                                number: *n2,
+                                digits: d2,
+                                expression: e2,
+                            },
+                        );
+                    }
+                    collector.into_candidates()
+                })
+                .collect()
+        });
+        let mut candidates = vec![];
+        let mut filtered = FilterCounts::default();
+        for (chunk, chunk_filtered) in chunks {
+            candidates.extend(chunk);
+            filtered.out_of_range += chunk_filtered.out_of_range;
+            filtered.already_searched += chunk_filtered.already_searched;
+        }
+        (candidates, filtered)
+    }
+}
+
+pub(crate) fn build_thread_pool(threads: usize) -> Option<Arc<ThreadPool>> {
+    if threads > 1 {
+        ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .ok()
+            .map(Arc::new)
+    } else {
+        None
+    }
+}
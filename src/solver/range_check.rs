@@ -1,28 +1,55 @@
 use super::Solver;
-use crate::{IntegralQuadratic, Number, RationalQuadratic};
+use crate::{GaussianRational, IntegralQuadratic, Number, RationalQuadratic};
 use num::rational::Rational64;
+use num::Signed;
 
 pub(super) trait RangeCheck<T: Number> {
     fn range_check(&self, _x: &T) -> bool;
 }
 
 impl<T: Number> RangeCheck<T> for Solver<T> {
-    default fn range_check(&self, _x: &T) -> bool {
-        true
+    default fn range_check(&self, x: &T) -> bool {
+        let magnitude = x.to_f64().abs();
+        magnitude <= 2f64.powi(self.limits.max_digits as i32)
+            && self
+                .limits
+                .max_magnitude
+                .is_none_or(|max| magnitude <= max as f64)
+            && self.is_allowed_value(x)
     }
 }
 
-impl RangeCheck<i64> for Solver<i64> {
+impl<T: Number> Solver<T> {
+    fn is_allowed_value(&self, x: &T) -> bool {
+        match (&self.limits.allowed_values, x.to_int()) {
+            (Some(values), Some(x_int)) => *x == self.target || values.contains(&x_int),
+            _ => true,
+        }
+    }
+
+    fn is_within_magnitude(&self, parts: &[i128]) -> bool {
+        self.limits
+            .max_magnitude
+            .is_none_or(|max| parts.iter().all(|x| -max <= *x && *x <= max))
+    }
+}
+
+impl RangeCheck<i128> for Solver<i128> {
     #[inline]
-    fn range_check(&self, x: &i64) -> bool {
+    fn range_check(&self, x: &i128) -> bool {
         *x <= 1 << self.limits.max_digits
+            && self.is_within_magnitude(&[*x])
+            && self.is_allowed_value(x)
     }
 }
 
 impl RangeCheck<Rational64> for Solver<Rational64> {
     #[inline]
     fn range_check(&self, x: &Rational64) -> bool {
-        *x.numer() <= 1 << self.limits.max_digits && *x.denom() <= 1 << self.limits.max_digits
+        *x.numer() <= 1 << self.limits.max_digits
+            && *x.denom() <= 1 << self.limits.max_digits
+            && self.is_within_magnitude(&[*x.numer() as i128, *x.denom() as i128])
+            && self.is_allowed_value(x)
     }
 }
 
@@ -31,6 +58,8 @@ impl RangeCheck<IntegralQuadratic> for Solver<IntegralQuadratic> {
     fn range_check(&self, x: &IntegralQuadratic) -> bool {
         x.integral_part() <= 1 << self.limits.max_digits
             && x.quadratic_power() <= self.limits.max_quadratic_power
+            && self.is_within_magnitude(&[x.integral_part() as i128])
+            && self.is_allowed_value(x)
     }
 }
 
@@ -40,5 +69,58 @@ impl RangeCheck<RationalQuadratic> for Solver<RationalQuadratic> {
         *x.rational_part().numer() <= 1 << self.limits.max_digits
             && *x.rational_part().denom() <= 1 << self.limits.max_digits
             && x.quadratic_power() <= self.limits.max_quadratic_power
+            && self.is_within_magnitude(&[
+                *x.rational_part().numer() as i128,
+                *x.rational_part().denom() as i128,
+            ])
+            && self.is_allowed_value(x)
+    }
+}
+
+impl RangeCheck<GaussianRational> for Solver<GaussianRational> {
+    #[inline]
+    fn range_check(&self, x: &GaussianRational) -> bool {
+        [x.real_part(), x.imaginary_part()].iter().all(|y| {
+            y.numer().abs() <= 1 << self.limits.max_digits
+                && *y.denom() <= 1 << self.limits.max_digits
+                && self.is_within_magnitude(&[*y.numer() as i128, *y.denom() as i128])
+        }) && self.is_allowed_value(x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::solver::{Limits, Solver};
+    use crate::Number;
+    use num::rational::Rational64;
+
+    fn stored_magnitudes<T: Number>(target: T) -> Vec<f64> {
+        let mut solver = Solver::<T>::new(
+            3,
+            Limits {
+                max_digits: 24,
+                max_factorial: 8,
+                max_magnitude: Some(100),
+                ..Limits::default()
+            },
+        );
+        solver.solve(target, Some(4));
+        solver
+            .states
+            .iter()
+            .flat_map(|(x, (expression, _))| vec![x.to_f64(), expression.eval_f64()])
+            .map(f64::abs)
+            .collect()
+    }
+
+    #[test]
+    fn magnitude_cap_bounds_every_tier() {
+        let magnitudes = stored_magnitudes(99i64);
+        assert!(!magnitudes.is_empty());
+        assert!(magnitudes.iter().all(|x| *x <= 100.0));
+        assert!(stored_magnitudes(99i128).iter().all(|x| *x <= 100.0));
+        assert!(stored_magnitudes(Rational64::from(99))
+            .iter()
+            .all(|x| *x <= 100.0));
     }
 }
@@ -1,5 +1,9 @@
-use super::{BinaryOperation, SearchState, Solver, State, UnaryOperation};
+use super::{BinaryOperation, Candidate, Collector, SearchState, Solver, State, UnaryOperation};
 use crate::Number;
+#[cfg(feature = "search-timing")]
+use std::time::{Duration, Instant};
+
+const CANCEL_CHECK_INTERVAL: usize = 4096;
 
 pub(super) trait Searcher<T: Number> {
     fn search(&mut self, digits: usize) -> bool;
@@ -8,6 +12,31 @@ pub(super) trait Searcher<T: Number> {
 
 impl<T: Number> Searcher<T> for Solver<T> {
     fn search(&mut self, digits: usize) -> bool {
+        #[cfg(feature = "search-timing")]
+        let start = Instant::now();
+        let found = self.search_depth(digits);
+        #[cfg(feature = "search-timing")]
+        {
+            let depth_times = &mut self.stats.depth_times;
+            if depth_times.len() <= digits {
+                depth_times.resize(digits + 1, Duration::default());
+            }
+            depth_times[digits] += start.elapsed();
+        }
+        found
+    }
+
+    default fn sort_states(&mut self, _digits: usize) {}
+}
+
+impl Searcher<i128> for Solver<i128> {
+    fn sort_states(&mut self, digits: usize) {
+        self.states_by_depth[digits].sort();
+    }
+}
+
+impl<T: Number> Solver<T> {
+    fn search_depth(&mut self, digits: usize) -> bool {
         match self.search_state {
             SearchState::None => {
                 self.search_state = SearchState::Concat;
@@ -18,7 +47,7 @@ impl<T: Number> Searcher<T> for Solver<T> {
         match self.search_state {
             SearchState::Concat => {
                 self.search_state = SearchState::ExtraState(0);
-                if self.concat(digits) {
+                if self.concat(digits) | self.decimal(digits) {
                     return true;
                 }
             }
@@ -46,10 +75,14 @@ impl<T: Number> Searcher<T> for Solver<T> {
                 for i in start..l {
                     self.search_state = SearchState::UnaryOperation(i + 1);
                     let number = self.states_by_depth[digits - 1][i];
+                    let expression = match self.expression_at(&number, digits - 1) {
+                        Some(expression) => expression,
+                        None => continue,
+                    };
                     if self.unary_operation(State {
                         number,
                         digits,
-                        expression: self.states.get(&number).unwrap().0.clone(),
+                        expression,
                     }) {
                         return true;
                     }
@@ -60,43 +93,11 @@ impl<T: Number> Searcher<T> for Solver<T> {
         }
         match self.search_state {
             SearchState::BinaryOperationOfDifferentDepth(start_depth, start_position) => {
-                for d1 in start_depth..((digits + 1) >> 1) {
-                    let d2 = digits - d1;
-                    let l1 = self.states_by_depth[d1].len();
-                    let l2 = self.states_by_depth[d2].len();
-                    for i in 0..l1 {
-                        if d1 == start_depth && i < start_position.0 {
-                            continue;
-                        }
-                        let n1 = self.states_by_depth[d1][i];
-                        let e1 = self.states.get(&n1).unwrap().0.clone();
-                        for j in 0..l2 {
-                            if d1 == start_depth && i == start_position.0 && j < start_position.1 {
-                                continue;
-                            }
-                            self.search_state =
-                                SearchState::BinaryOperationOfDifferentDepth(d1, (i, j + 1));
-                            let n2 = self.states_by_depth[d2][j];
-                            if self.binary_operation(
-                                State {
-                                    number: n1,
-                                    digits: d1,
-                                    expression: e1.clone(),
-                                },
-                                State {
-                                    number: n2,
-                                    digits: d2,
-                                    expression: self.states.get(&n2).unwrap().0.clone(),
-                                },
-                            ) {
-                                return true;
-                            }
-                        }
-                        self.search_state =
-                            SearchState::BinaryOperationOfDifferentDepth(d1, (i + 1, 0));
-                    }
-                    self.search_state =
-                        SearchState::BinaryOperationOfDifferentDepth(d1 + 1, (0, 0));
+                if self.search_different_depth(digits, start_depth, start_position) {
+                    return true;
+                }
+                if self.is_cancelled() {
+                    return false;
                 }
                 self.search_state = SearchState::BinaryOperationOfSameDepth((0, 0));
             }
@@ -104,51 +105,152 @@ impl<T: Number> Searcher<T> for Solver<T> {
         }
         match self.search_state {
             SearchState::BinaryOperationOfSameDepth(start_position) => {
-                if digits % 2 == 0 {
-                    let d = digits >> 1;
-                    let l = self.states_by_depth[d].len();
-                    for i in start_position.0..l {
-                        let n1 = self.states_by_depth[d][i];
-                        let e1 = self.states.get(&n1).unwrap().0.clone();
-                        for j in i..l {
-                            if i == start_position.0 && j < start_position.1 {
-                                continue;
-                            }
-                            self.search_state = SearchState::BinaryOperationOfSameDepth((i, j + 1));
-                            let n2 = self.states_by_depth[d][j];
-                            if self.binary_operation(
-                                State {
-                                    number: n1,
-                                    digits: d,
-                                    expression: e1.clone(),
-                                },
-                                State {
-                                    number: n2,
-                                    digits: d,
-                                    expression: self.states.get(&n2).unwrap().0.clone(),
-                                },
-                            ) {
-                                return true;
-                            }
-                        }
-                        self.search_state = SearchState::BinaryOperationOfSameDepth((i + 1, i + 1));
-                    }
+                if digits.is_multiple_of(2) && self.search_same_depth(digits >> 1, start_position) {
+                    return true;
+                }
+                if self.is_cancelled() {
+                    return false;
                 }
                 self.search_state = SearchState::Finish;
             }
             _ => {}
         }
+        if self.limits.max_cache_entries.is_some() {
+            self.compact_states_by_depth();
+        }
         self.sort_states(digits);
         self.depth_searched = digits;
         self.search_state = SearchState::None;
         false
     }
 
-    default fn sort_states(&mut self, _digits: usize) {}
-}
+    fn search_different_depth(
+        &mut self,
+        digits: usize,
+        start_depth: usize,
+        start_position: (usize, usize),
+    ) -> bool {
+        #[cfg(feature = "parallel")]
+        {
+            if self.thread_pool.is_some() {
+                return self.search_different_depth_parallel(digits, start_depth, start_position);
+            }
+        }
+        let mut candidates = vec![];
+        let mut operations = 0usize;
+        for d1 in start_depth..((digits + 1) >> 1) {
+            let d2 = digits - d1;
+            let l1 = self.states_by_depth[d1].len();
+            let l2 = self.states_by_depth[d2].len();
+            for i in 0..l1 {
+                if d1 == start_depth && i < start_position.0 {
+                    continue;
+                }
+                let n1 = self.states_by_depth[d1][i];
+                let e1 = match self.expression_at(&n1, d1) {
+                    Some(expression) => expression,
+                    None => continue,
+                };
+                for j in 0..l2 {
+                    if d1 == start_depth && i == start_position.0 && j < start_position.1 {
+                        continue;
+                    }
+                    operations += 1;
+                    if operations.is_multiple_of(CANCEL_CHECK_INTERVAL) && self.is_cancelled() {
+                        return false;
+                    }
+                    self.search_state =
+                        SearchState::BinaryOperationOfDifferentDepth(d1, (i, j + 1));
+                    let n2 = self.states_by_depth[d2][j];
+                    let e2 = match self.expression_at(&n2, d2) {
+                        Some(expression) => expression,
+                        None => continue,
+                    };
+                    if self.binary_operation_of(
+                        State {
+                            number: n1,
+                            digits: d1,
+                            expression: e1.clone(),
+                        },
+                        State {
+                            number: n2,
+                            digits: d2,
+                            expression: e2,
+                        },
+                        &mut candidates,
+                    ) {
+                        return true;
+                    }
+                }
+                self.search_state = SearchState::BinaryOperationOfDifferentDepth(d1, (i + 1, 0));
+            }
+            self.search_state = SearchState::BinaryOperationOfDifferentDepth(d1 + 1, (0, 0));
+        }
+        false
+    }
 
-impl Searcher<i64> for Solver<i64> {
-    fn sort_states(&mut self, digits: usize) {
-        self.states_by_depth[digits].sort();
+    fn search_same_depth(&mut self, d: usize, start_position: (usize, usize)) -> bool {
+        #[cfg(feature = "parallel")]
+        {
+            if self.thread_pool.is_some() {
+                return self.search_same_depth_parallel(d, start_position);
+            }
+        }
+        let mut candidates = vec![];
+        let mut operations = 0usize;
+        let l = self.states_by_depth[d].len();
+        for i in start_position.0..l {
+            let n1 = self.states_by_depth[d][i];
+            let e1 = match self.expression_at(&n1, d) {
+                Some(expression) => expression,
+                None => continue,
+            };
+            for j in i..l {
+                if i == start_position.0 && j < start_position.1 {
+                    continue;
+                }
+                operations += 1;
+                if operations.is_multiple_of(CANCEL_CHECK_INTERVAL) && self.is_cancelled() {
+                    return false;
+                }
+                self.search_state = SearchState::BinaryOperationOfSameDepth((i, j + 1));
+                let n2 = self.states_by_depth[d][j];
+                let e2 = match self.expression_at(&n2, d) {
+                    Some(expression) => expression,
+                    None => continue,
+                };
+                if self.binary_operation_of(
+                    State {
+                        number: n1,
+                        digits: d,
+                        expression: e1.clone(),
+                    },
+                    State {
+                        number: n2,
+                        digits: d,
+                        expression: e2,
+                    },
+                    &mut candidates,
+                ) {
+                    return true;
+                }
+            }
+            self.search_state = SearchState::BinaryOperationOfSameDepth((i + 1, i + 1));
+        }
+        false
+    }
+
+    fn binary_operation_of(
+        &mut self,
+        x: State<T>,
+        y: State<T>,
+        candidates: &mut Vec<Candidate<T>>,
+    ) -> bool {
+        let mut collector = Collector::new(self, std::mem::take(candidates));
+        collector.binary_operation(x, y);
+        let (collected, filtered) = collector.into_candidates();
+        *candidates = collected;
+        self.record_filtered(filtered);
+        self.merge_candidates(candidates)
     }
 }
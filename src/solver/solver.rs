@@ -1,37 +1,52 @@
-use super::{Limits, RangeCheck, SearchState, Searcher, Solver, State, UnaryOperation};
-use crate::{Expression, Number};
-use std::collections::HashMap;
-use std::rc::Rc;
+use super::{
+    AllowedOps, BinaryOperation, Candidate, Collector, FilterCounts, Inconsistency, Limits,
+    MeetInTheMiddle, RangeCheck, SearchState, SearchStats, Searcher, Solver, SolverState, State,
+    UnaryOperation,
+};
+use crate::number_theory::{prime_factors, primes_up_to};
+use crate::progress_observer::SharedObserver;
+use crate::{Expression, Number, OpKind, RationalQuadratic, Rc, MAX_QUADRATIC_POWER};
+use std::collections::{HashMap, VecDeque};
+use std::convert::TryInto;
 use std::slice::Iter;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 impl<T: Number> Solver<T> {
     pub fn new(n: i64, limits: Limits) -> Self {
+        assert!(
+            limits.max_quadratic_power <= MAX_QUADRATIC_POWER,
+            "max_quadratic_power must not exceed {}",
+            MAX_QUADRATIC_POWER
+        );
         Self {
             n,
             target: T::zero(),
             states: HashMap::new(),
+            insertion_order: VecDeque::new(),
             states_by_depth: vec![],
             extra_states_by_depth: vec![],
             depth_searched: 0,
             search_state: SearchState::None,
+            primes: primes_up_to(limits.max_prime),
+            cancel_flag: None,
+            observer: None,
+            captured_solutions: None,
+            prime_signature: None,
             limits,
             progressive: false,
             new_numbers: vec![],
+            peak_size: 0,
+            stats: SearchStats::default(),
+            #[cfg(feature = "parallel")]
+            thread_pool: None,
         }
     }
 
     pub fn new_progressive(n: i64, limits: Limits) -> Self {
         Self {
-            n,
-            target: T::zero(),
-            states: HashMap::new(),
-            states_by_depth: vec![],
-            extra_states_by_depth: vec![],
-            depth_searched: 0,
-            search_state: SearchState::None,
-            limits,
             progressive: true,
-            new_numbers: vec![],
+            ..Self::new(n, limits)
         }
     }
 
@@ -39,6 +54,10 @@ impl<T: Number> Solver<T> {
     pub(crate) fn clone_non_pregressive_from(&mut self, source: &Self) {
         self.clone_from(source);
         self.progressive = false;
+        self.stats = SearchStats {
+            table_size: self.states.len(),
+            ..SearchStats::default()
+        };
     }
 
     pub fn solve(
@@ -46,7 +65,13 @@ impl<T: Number> Solver<T> {
         target: T,
         max_depth: Option<usize>,
     ) -> Option<(Rc<Expression>, usize)> {
+        if self.limits.free_negation && target.is_negative() {
+            return self
+                .solve(-target, max_depth)
+                .map(|(expression, digits)| (Expression::from_negate(expression), digits));
+        }
         self.target = target;
+        self.prime_signature = self.prime_signature_for(max_depth);
         if let Some((expression, digits)) = self.states.get(&self.target) {
             return if max_depth.unwrap_or(usize::MAX) >= *digits {
                 Some((expression.clone(), *digits))
@@ -55,41 +80,285 @@ impl<T: Number> Solver<T> {
             };
         }
         for digits in self.depth_searched + 1..=max_depth.unwrap_or(usize::MAX) {
+            if self.is_cancelled() || self.is_exhausted() {
+                break;
+            }
+            if let Some(expression) = self.try_meet_in_the_middle(digits) {
+                if self.try_insert(self.target, digits, || expression) {
+                    return Some(self.states.get(&self.target)?.clone());
+                }
+            }
             if self.search(digits) {
+                if self.replaces_equal_depth() {
+                    while self.depth_searched < digits && !self.is_cancelled() {
+                        self.search(digits);
+                    }
+                }
                 return Some(self.states.get(&self.target)?.clone());
             }
         }
         None
     }
 
+    fn prime_signature_for(&self, max_depth: Option<usize>) -> Option<(usize, Vec<i128>)> {
+        if !self.limits.prune_by_prime_signature
+            || self.progressive
+            || [
+                AllowedOps::SUBFACTORIAL,
+                AllowedOps::NTH_PRIME,
+                AllowedOps::PRIME_PI,
+                AllowedOps::LOG,
+            ]
+            .iter()
+            .any(|op| self.allows(*op))
+        {
+            return None;
+        }
+        let target = self.target.to_int()?;
+        if target.abs() <= 1 {
+            return None;
+        }
+        Some((max_depth?, prime_factors(target.into())?))
+    }
+
+    fn try_meet_in_the_middle(&self, digits: usize) -> Option<Rc<Expression>> {
+        if !self.limits.meet_in_the_middle
+            || self.replaces_equal_depth()
+            || digits != self.depth_searched + 1
+        {
+            return None;
+        }
+        match self.search_state {
+            SearchState::None => self.meet_in_the_middle(digits),
+            _ => None,
+        }
+    }
+
+    pub fn grow_to_depth(&mut self, depth: usize) {
+        while self.depth_searched < depth && !self.is_cancelled() {
+            self.search(self.depth_searched + 1);
+        }
+    }
+
+    pub fn set_cancel_flag(&mut self, cancel_flag: Arc<AtomicBool>) {
+        self.cancel_flag = Some(cancel_flag);
+    }
+
+    pub(crate) fn set_observer(&mut self, observer: Option<SharedObserver>) {
+        self.observer = observer;
+    }
+
+    #[inline]
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancel_flag
+            .as_ref()
+            .is_some_and(|flag| flag.load(Ordering::Relaxed))
+    }
+
+    pub fn save_state(&self) -> SolverState<T> {
+        SolverState {
+            target: self.target,
+            states: self
+                .states
+                .iter()
+                .map(|(x, (expression, digits))| (*x, expression.clone(), *digits))
+                .collect(),
+            insertion_order: self.insertion_order.clone(),
+            states_by_depth: self.states_by_depth.clone(),
+            extra_states_by_depth: self.extra_states_by_depth.clone(),
+            depth_searched: self.depth_searched,
+            search_state: self.search_state.clone(),
+            new_numbers: self.new_numbers.clone(),
+            peak_size: self.peak_size,
+        }
+    }
+
+    pub fn restore_state(&mut self, state: SolverState<T>) {
+        self.target = state.target;
+        self.states = state
+            .states
+            .into_iter()
+            .map(|(x, expression, digits)| (x, (expression, digits)))
+            .collect();
+        self.insertion_order = state.insertion_order;
+        self.states_by_depth = state.states_by_depth;
+        self.extra_states_by_depth = state.extra_states_by_depth;
+        self.depth_searched = state.depth_searched;
+        self.search_state = state.search_state;
+        self.new_numbers = state.new_numbers;
+        self.peak_size = state.peak_size;
+    }
+
+    #[inline]
+    pub fn peak_size(&self) -> usize {
+        self.peak_size
+    }
+
+    #[inline]
+    pub fn stats(&self) -> &SearchStats {
+        &self.stats
+    }
+
     #[inline]
     pub fn get_solution(&self, x: &T) -> Option<&(Rc<Expression>, usize)> {
         self.states.get(x)
     }
 
+    #[inline]
+    pub(super) fn expression_at(&self, number: &T, depth: usize) -> Option<Rc<Expression>> {
+        match self.states.get(number) {
+            Some((expression, digits)) if *digits == depth => Some(expression.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn reachable_at_depth(
+        &self,
+        digits: usize,
+    ) -> impl Iterator<Item = (&T, &Rc<Expression>)> + '_ {
+        self.states_by_depth
+            .get(digits)
+            .into_iter()
+            .flatten()
+            .filter_map(move |x| match self.states.get(x) {
+                Some((expression, x_digits)) if *x_digits == digits => Some((x, expression)),
+                _ => None,
+            })
+    }
+
+    pub fn solutions_at(&mut self, target: T, digits: usize) -> Vec<Rc<Expression>> {
+        let mut solutions = self.binary_solutions_at(target, digits);
+        self.captured_solutions = Some((target, vec![]));
+        self.concat(digits);
+        self.decimal(digits);
+        let numbers = self
+            .states_by_depth
+            .get(digits)
+            .cloned()
+            .unwrap_or_default();
+        for number in numbers {
+            if number == target {
+                continue;
+            }
+            if let Some(expression) = self.expression_at(&number, digits) {
+                self.unary_operations(&State {
+                    number,
+                    digits,
+                    expression,
+                });
+            }
+        }
+        if let Some((_, captured)) = self.captured_solutions.take() {
+            solutions.extend(captured);
+        }
+        solutions
+    }
+
+    fn binary_solutions_at(&self, target: T, digits: usize) -> Vec<Rc<Expression>> {
+        let mut collector = Collector::for_target(self, target);
+        let state = |depth: usize, number: T| {
+            Some(State {
+                number,
+                digits: depth,
+                expression: self.expression_at(&number, depth)?,
+            })
+        };
+        for d1 in 1..=digits >> 1 {
+            let d2 = digits - d1;
+            if d2 >= self.states_by_depth.len() {
+                continue;
+            }
+            for (i, n1) in self.states_by_depth[d1].iter().enumerate() {
+                let start = if d1 == d2 { i } else { 0 };
+                for n2 in &self.states_by_depth[d2][start..] {
+                    if let (Some(x), Some(y)) = (state(d1, *n1), state(d2, *n2)) {
+                        collector.binary_operation(x, y);
+                    }
+                }
+            }
+        }
+        collector
+            .into_candidates()
+            .0
+            .into_iter()
+            .map(|(_, _, expression)| expression)
+            .collect()
+    }
+
     pub fn try_insert(
         &mut self,
         x: T,
         digits: usize,
         expression_fn: impl FnOnce() -> Rc<Expression>,
     ) -> bool {
-        if !self.range_check(&x) || self.states.contains_key(&x) {
+        if let Some((target, captured)) = &mut self.captured_solutions {
+            if x == *target {
+                captured.push(expression_fn());
+            }
             return false;
         }
+        self.stats.candidates += 1;
+        if !self.range_check(&x) {
+            self.stats.out_of_range += 1;
+            return false;
+        }
+        if let Some((_, x_digits)) = self.states.get(&x) {
+            if *x_digits <= digits {
+                self.stats.already_searched += 1;
+                self.stats.skipped_follow_ups += 1;
+                if self.replaces_equal_depth() && *x_digits == digits {
+                    self.try_replace(x, expression_fn());
+                }
+                return false;
+            }
+        }
         let expression = expression_fn();
-        let mut found = false;
-        if self.insert(x, digits, expression.clone()) {
-            found = true;
+        if !self.is_within_operator_caps(&expression) {
+            return false;
+        }
+        if x == self.target && !self.is_acceptable_solution(&expression) {
+            return false;
         }
-        let state = State {
+        let found = self.insert(x, digits, expression.clone());
+        let found_by_unary = self.unary_operations(&State {
             number: x,
             digits,
             expression,
-        };
-        if self.sqrt(&state) {
+        });
+        found || found_by_unary
+    }
+
+    fn unary_operations(&mut self, state: &State<T>) -> bool {
+        let x = state.number;
+        let mut found = false;
+        if self.allows(AllowedOps::SQRT) && self.sqrt(state) {
             found = true;
         }
-        if x.is_int() && self.factorial(&state) {
+        if self.allows(AllowedOps::ROOT) && self.root(state) {
+            found = true;
+        }
+        if self.allows(AllowedOps::FACTORIAL) && x.is_int() && self.factorial(state) {
+            found = true;
+        }
+        if self.allows(AllowedOps::SUBFACTORIAL) && x.is_int() && self.subfactorial(state) {
+            found = true;
+        }
+        if self.allows(AllowedOps::DOUBLE_FACTORIAL) && x.is_int() && self.double_factorial(state) {
+            found = true;
+        }
+        if self.allows(AllowedOps::NTH_PRIME) && x.is_int() && self.nth_prime(state) {
+            found = true;
+        }
+        if self.allows(AllowedOps::PRIME_PI) && x.is_int() && self.prime_pi(state) {
+            found = true;
+        }
+        if self.allows(AllowedOps::ROUND) && !x.is_int() && self.round(state) {
+            found = true;
+        }
+        if self.allows(AllowedOps::PERCENT) && self.percent(state) {
+            found = true;
+        }
+        if self.allows(AllowedOps::LOG) && self.log(state) {
             found = true;
         }
         found
@@ -103,7 +372,7 @@ impl<T: Number> Solver<T> {
     }
 
     #[inline]
-    pub(crate) fn new_numbers(&self) -> NewNumberIterator<T> {
+    pub(crate) fn new_numbers(&self) -> NewNumberIterator<'_, T> {
         NewNumberIterator {
             solver: self,
             iter: self.new_numbers.iter(),
@@ -115,8 +384,153 @@ impl<T: Number> Solver<T> {
         self.new_numbers.clear();
     }
 
+    fn evict(&mut self, max_cache_entries: usize) {
+        let mut pinned = 0;
+        while self.states.len() > max_cache_entries && pinned < self.insertion_order.len() {
+            let x = self.insertion_order.pop_front().unwrap();
+            match self.states.get(&x) {
+                Some((_, digits)) if *digits == 1 || x == self.target => {
+                    self.insertion_order.push_back(x);
+                    pinned += 1;
+                }
+                Some(_) => {
+                    self.states.remove(&x);
+                    pinned = 0;
+                }
+                None => {}
+            }
+        }
+    }
+
+    pub(super) fn compact_states_by_depth(&mut self) {
+        let states = &self.states;
+        for (depth, numbers) in self.states_by_depth.iter_mut().enumerate() {
+            numbers.retain(|x| states.get(x).is_some_and(|(_, digits)| *digits == depth));
+        }
+    }
+
+    #[inline]
+    pub(super) fn replaces_equal_depth(&self) -> bool {
+        self.limits.prefer_small_intermediates || self.limits.prefer_fewer_operators
+    }
+
+    fn try_replace(&mut self, x: T, expression: Rc<Expression>) {
+        let magnitude = |expression: &Expression| {
+            expression
+                .max_intermediate()
+                .map_or(f64::INFINITY, |y| y.to_f64().abs())
+        };
+        let (old_expression, digits) = self.states.get(&x).unwrap();
+        let operators = expression.operator_count();
+        let old_operators = old_expression.operator_count();
+        let is_better = if self.limits.prefer_fewer_operators && operators != old_operators {
+            operators < old_operators
+        } else {
+            self.limits.prefer_small_intermediates
+                && magnitude(&expression) < magnitude(old_expression)
+        };
+        if is_better
+            && self.is_within_operator_caps(&expression)
+            && (x != self.target || self.is_acceptable_solution(&expression))
+        {
+            let digits = *digits;
+            self.states.insert(x, (expression, digits));
+        }
+    }
+
+    #[inline]
+    pub(super) fn allows(&self, ops: AllowedOps) -> bool {
+        self.limits.allowed_ops.contains(ops)
+    }
+
+    pub(super) fn can_concat(&self, digits: usize) -> bool {
+        (digits == 1 || self.allows(AllowedOps::CONCAT))
+            && digits as f64 * 10f64.log2() - 9f64.log2() <= self.limits.max_digits as f64
+    }
+
+    pub(crate) fn is_exhausted(&self) -> bool {
+        let depth = self.depth_searched;
+        depth > 0
+            && !self.can_concat(depth + 1)
+            && (depth / 2 + 1..=depth).all(|digits| {
+                self.states_by_depth
+                    .get(digits)
+                    .is_none_or(|states| states.is_empty())
+            })
+    }
+
+    pub(crate) fn nearest_int(&self, target: i128) -> Option<(i128, Rc<Expression>, usize)> {
+        self.states
+            .iter()
+            .filter_map(|(x, (expression, digits))| {
+                Some((x.to_int()? as i128, expression, *digits))
+            })
+            .min_by_key(|(x, _, digits)| ((x - target).abs(), *digits))
+            .map(|(x, expression, digits)| (x, expression.clone(), digits))
+    }
+
+    fn is_within_operator_caps(&self, expression: &Expression) -> bool {
+        if self.limits.max_factorials_per_expression.is_none()
+            && self.limits.max_sqrts_per_expression.is_none()
+            && self.limits.max_sqrt_nesting.is_none()
+        {
+            return true;
+        }
+        if let Some(max_sqrt_nesting) = self.limits.max_sqrt_nesting {
+            if expression.sqrt_nesting() > max_sqrt_nesting {
+                return false;
+            }
+        }
+        let counts = expression.operator_counts();
+        let within_cap = |op_kind: OpKind, cap: Option<usize>| {
+            cap.is_none_or(|cap| counts.get(&op_kind).map_or(0, |count| *count) <= cap)
+        };
+        within_cap(OpKind::Factorial, self.limits.max_factorials_per_expression)
+            && within_cap(OpKind::Sqrt, self.limits.max_sqrts_per_expression)
+    }
+
+    fn is_acceptable_solution(&self, expression: &Expression) -> bool {
+        if let Some(max_distinct_operators) = self.limits.max_distinct_operators {
+            if expression.operator_counts().len() > max_distinct_operators {
+                return false;
+            }
+        }
+        if let Some(max_height) = self.limits.max_height {
+            if expression.height() > max_height {
+                return false;
+            }
+        }
+        true
+    }
+
+    pub(super) fn record_filtered(&mut self, filtered: FilterCounts) {
+        self.stats.candidates += filtered.out_of_range + filtered.already_searched;
+        self.stats.out_of_range += filtered.out_of_range;
+        self.stats.already_searched += filtered.already_searched;
+    }
+
+    pub(super) fn merge_candidates(&mut self, candidates: &mut Vec<Candidate<T>>) -> bool {
+        let mut found = false;
+        for (x, digits, expression) in candidates.drain(..) {
+            if self.try_insert(x, digits, || expression) {
+                found = true;
+            }
+        }
+        found
+    }
+
     fn insert(&mut self, x: T, digits: usize, expression: Rc<Expression>) -> bool {
-        self.states.insert(x, (expression, digits));
+        let discovered = self.states.insert(x, (expression, digits)).is_none();
+        if let Some(max_cache_entries) = self.limits.max_cache_entries {
+            if !discovered {
+                self.insertion_order.retain(|y| *y != x);
+            }
+            self.insertion_order.push_back(x);
+            self.evict(max_cache_entries);
+        }
+        self.peak_size = self.peak_size.max(self.states.len());
+        self.stats.inserted += 1;
+        self.stats.table_size = self.states.len();
         if self.states_by_depth.len() <= digits {
             self.states_by_depth.resize(digits + 1, vec![]);
         }
@@ -124,10 +538,45 @@ impl<T: Number> Solver<T> {
         if self.progressive {
             self.new_numbers.push(x);
         }
+        if let Some(observer) = &self.observer {
+            observer.lock().unwrap().on_number_inserted(&x, digits);
+        }
         x == self.target
     }
 }
 
+impl Solver<i128> {
+    pub fn max_reachable(&mut self, digits: usize) -> Option<(i128, Rc<Expression>)> {
+        self.grow_to_depth(digits);
+        self.states
+            .iter()
+            .filter(|(_, (_, x_digits))| *x_digits <= digits)
+            .max_by_key(|(x, _)| **x)
+            .map(|(x, (expression, _))| (*x, expression.clone()))
+    }
+}
+
+impl<T: Number + TryInto<RationalQuadratic>> Solver<T> {
+    pub fn validate(&self) -> Vec<Inconsistency> {
+        let mut inconsistencies = vec![];
+        for (x, (expression, digits)) in self.states.iter() {
+            let number: Option<RationalQuadratic> = (*x).try_into().ok();
+            let evaluated = expression.evaluate();
+            let evaluated_digits = expression.cost_in_base(10);
+            if number.is_none() || evaluated != number || evaluated_digits != *digits {
+                inconsistencies.push(Inconsistency {
+                    number,
+                    digits: *digits,
+                    expression: expression.clone(),
+                    evaluated,
+                    evaluated_digits,
+                });
+            }
+        }
+        inconsistencies
+    }
+}
+
 pub(crate) struct NewNumberIterator<'a, T: Number> {
     solver: &'a Solver<T>,
     iter: Iter<'a, T>,
@@ -137,8 +586,80 @@ impl<'a, T: Number> Iterator for NewNumberIterator<'a, T> {
     type Item = (&'a T, &'a Rc<Expression>, usize);
 
     fn next(&mut self) -> Option<Self::Item> {
-        let x = self.iter.next()?;
-        let (expression, digits) = self.solver.states.get(x)?;
-        Some((x, expression, *digits))
+        loop {
+            let x = self.iter.next()?;
+            if let Some((expression, digits)) = self.solver.states.get(x) {
+                return Some((x, expression, *digits));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eviction_keeps_replaced_entries() {
+        let mut solver = Solver::<i128>::new(
+            4,
+            Limits {
+                max_cache_entries: Some(3),
+                ..Limits::default()
+            },
+        );
+        for x in [10, 20, 30] {
+            solver.insert(x, 3, Expression::from_number(x as i64));
+        }
+        solver.insert(10, 2, Expression::from_number(10));
+        solver.insert(40, 3, Expression::from_number(40));
+        assert_eq!(solver.states.len(), 3);
+        assert_eq!(solver.insertion_order, [30, 10, 40]);
+        assert_eq!(solver.get_solution(&10).map(|(_, digits)| *digits), Some(2));
+        assert!(solver.get_solution(&20).is_none());
+    }
+
+    #[test]
+    fn known_values_skip_follow_ups() {
+        let mut solver = Solver::<i128>::new(
+            4,
+            Limits {
+                max_digits: 16,
+                ..Limits::default()
+            },
+        );
+        let depth_of =
+            |solver: &Solver<i128>, x| solver.get_solution(&x).map(|(_, digits)| *digits);
+        solver.try_insert(81, 3, || Expression::from_number(81));
+        assert_eq!(depth_of(&solver, 3), Some(3));
+        assert_eq!(solver.stats().skipped_follow_ups, 0);
+        solver.try_insert(81, 4, || Expression::from_number(81));
+        assert_eq!(solver.stats().skipped_follow_ups, 1);
+        assert_eq!(depth_of(&solver, 81), Some(3));
+        solver.try_insert(81, 2, || Expression::from_number(81));
+        assert_eq!(depth_of(&solver, 81), Some(2));
+        assert_eq!(depth_of(&solver, 9), Some(2));
+        assert_eq!(depth_of(&solver, 3), Some(2));
+    }
+
+    #[test]
+    fn skipping_follow_ups_keeps_minimal_depths() {
+        let limits = Limits {
+            max_digits: 16,
+            max_factorial: 4,
+            ..Limits::default()
+        };
+        let mut shared = Solver::<i128>::new(4, limits.clone());
+        shared.grow_to_depth(4);
+        assert!(shared.stats().skipped_follow_ups > 0);
+        for target in 1..=60 {
+            let fresh = Solver::<i128>::new(4, limits.clone()).solve(target, Some(4));
+            assert_eq!(
+                shared.get_solution(&target).map(|(_, digits)| *digits),
+                fresh.map(|(_, digits)| digits),
+                "{}",
+                target
+            );
+        }
     }
 }
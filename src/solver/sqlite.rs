@@ -0,0 +1,30 @@
+use super::Solver;
+use rusqlite::{params, Connection};
+use std::convert::TryFrom;
+use std::path::Path;
+
+impl Solver<i128> {
+    pub fn export_sqlite<P: AsRef<Path>>(&self, path: P) -> rusqlite::Result<()> {
+        let mut connection = Connection::open(path)?;
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS numbers (
+                value INTEGER PRIMARY KEY,
+                digits INTEGER NOT NULL,
+                expression TEXT NOT NULL
+            )",
+            params![],
+        )?;
+        let transaction = connection.transaction()?;
+        {
+            let mut statement = transaction.prepare(
+                "INSERT OR REPLACE INTO numbers (value, digits, expression) VALUES (?1, ?2, ?3)",
+            )?;
+            for (x, (expression, digits)) in self.states.iter() {
+                if let Ok(x) = i64::try_from(*x) {
+                    statement.execute(params![x, *digits as i64, expression.to_string()])?;
+                }
+            }
+        }
+        transaction.commit()
+    }
+}
@@ -1,9 +1,12 @@
-use super::{Solver, State};
-use crate::number_theory::{factorial, try_sqrt};
-use crate::{Expression, IntegralQuadratic, Number, RationalQuadratic};
+use super::{AllowedOps, Solver, State};
+use crate::number_theory::{
+    double_factorial, exact_log, factorial, number_of_digits, round_rational, subfactorial,
+};
+use crate::{Expression, GaussianRational, IntegralQuadratic, Number, RationalQuadratic, Rc};
 use num::rational::Rational64;
-use num::traits::Inv;
-use std::rc::Rc;
+use num::traits::{CheckedAdd, CheckedMul, CheckedSub, Inv};
+use num::Signed;
+use std::convert::TryFrom;
 
 fn is_single_digit(expression: &Expression) -> bool {
     match expression {
@@ -15,11 +18,75 @@ fn is_single_digit(expression: &Expression) -> bool {
     }
 }
 
+fn factorial_digits(n: i64) -> f64 {
+    (2..=n).map(|x| (x as f64).log2()).sum()
+}
+
+fn double_factorial_digits(n: i64) -> f64 {
+    (2..=n).rev().step_by(2).map(|x| (x as f64).log2()).sum()
+}
+
+impl<T: Number + From<Rational64>> Solver<T> {
+    fn insert_decimals(&mut self, digits: usize) -> bool {
+        if !self.can_concat(digits) {
+            return false;
+        }
+        let x = (10i64.pow(digits as u32) - 1) / 9 * self.n;
+        let denominator = 10i64.pow(number_of_digits(x) as u32);
+        let mut found = false;
+        if self.allows(AllowedOps::DECIMAL)
+            && self.try_insert(Rational64::new(x, denominator).into(), digits, || {
+                Expression::from_decimal(x)
+            })
+        {
+            found = true;
+        }
+        if self.allows(AllowedOps::REPEATING_DECIMAL)
+            && self.try_insert(Rational64::new(x, denominator - 1).into(), digits, || {
+                Expression::from_repeating_decimal(x)
+            })
+        {
+            found = true;
+        }
+        found
+    }
+
+    fn insert_percent(&mut self, x: &State<T>) -> bool
+    where
+        T: CheckedMul,
+    {
+        let mut depth = 0;
+        let mut expression = &x.expression;
+        while let Some(y) = expression.to_percent() {
+            depth += 1;
+            expression = y;
+        }
+        if depth >= self.limits.max_percent_depth {
+            return false;
+        }
+        match x.number.checked_mul(&Rational64::new(1, 100).into()) {
+            Some(y) => self.try_insert(y, x.digits, || {
+                Expression::from_percent(x.expression.clone())
+            }),
+            None => false,
+        }
+    }
+}
+
 pub(super) trait UnaryOperation<T: Number> {
     fn unary_operation(&mut self, x: State<T>) -> bool;
     fn concat(&mut self, digits: usize) -> bool;
+    fn decimal(&mut self, digits: usize) -> bool;
     fn sqrt(&mut self, x: &State<T>) -> bool;
+    fn root(&mut self, x: &State<T>) -> bool;
     fn factorial(&mut self, x: &State<T>) -> bool;
+    fn subfactorial(&mut self, x: &State<T>) -> bool;
+    fn double_factorial(&mut self, x: &State<T>) -> bool;
+    fn nth_prime(&mut self, x: &State<T>) -> bool;
+    fn prime_pi(&mut self, x: &State<T>) -> bool;
+    fn round(&mut self, x: &State<T>) -> bool;
+    fn percent(&mut self, x: &State<T>) -> bool;
+    fn log(&mut self, x: &State<T>) -> bool;
     fn division_diff_one(
         &mut self,
         x: T,
@@ -35,6 +102,9 @@ impl<T: Number> UnaryOperation<T> for Solver<T> {
         {
             return false;
         }
+        if !self.allows(AllowedOps::ADD | AllowedOps::SUBTRACT | AllowedOps::DIVIDE) {
+            return false;
+        }
         let (numerator, denominator) = x.expression.to_divide().unwrap();
         if is_single_digit(denominator) {
             return self.division_diff_one(
@@ -73,22 +143,113 @@ impl<T: Number> UnaryOperation<T> for Solver<T> {
     }
 
     fn concat(&mut self, digits: usize) -> bool {
-        if digits as f64 * 10f64.log2() - 9f64.log2() > self.limits.max_digits as f64 {
+        if !self.can_concat(digits) {
             return false;
         }
         let x = (10i64.pow(digits as u32) - 1) / 9 * self.n;
         self.try_insert(x.into(), digits, || Expression::from_number(x))
     }
 
-    default fn sqrt(&mut self, _x: &State<T>) -> bool {
+    default fn decimal(&mut self, _digits: usize) -> bool {
         false
     }
 
+    fn sqrt(&mut self, x: &State<T>) -> bool {
+        match x.number.try_sqrt() {
+            Some(y) if y.quadratic_power() <= self.limits.max_quadratic_power => {
+                self.try_insert(y, x.digits, || {
+                    Expression::from_sqrt(x.expression.clone(), 1)
+                })
+            }
+            _ => false,
+        }
+    }
+
+    fn root(&mut self, x: &State<T>) -> bool {
+        if x.number.is_zero() || x.number.abs().is_one() {
+            return false;
+        }
+        let mut found = false;
+        for degree in 3..=self.limits.max_root_degree {
+            if let Some(y) = x.number.try_nth_root(degree) {
+                if self.try_insert(y, x.digits, || {
+                    Expression::from_root(x.expression.clone(), degree)
+                }) {
+                    found = true;
+                }
+            }
+        }
+        found
+    }
+
     fn factorial(&mut self, x: &State<T>) -> bool {
         if let Some(n) = x.number.to_int() {
-            if n < self.limits.max_factorial as i64 {
-                self.try_insert(factorial(n).into(), x.digits, || {
-                    Expression::from_factorial(x.expression.clone())
+            if n < self.limits.max_factorial as i64
+                && factorial_digits(n) <= self.limits.max_digits as f64
+            {
+                if let Some(result) = factorial(n) {
+                    return self.try_insert(result.into(), x.digits, || {
+                        Expression::from_factorial(x.expression.clone())
+                    });
+                }
+            }
+        }
+        false
+    }
+
+    fn subfactorial(&mut self, x: &State<T>) -> bool {
+        if let Some(n) = x.number.to_int() {
+            if n > 1
+                && n < self.limits.max_subfactorial
+                && factorial_digits(n) <= self.limits.max_digits as f64
+            {
+                if let Some(result) = subfactorial(n) {
+                    return self.try_insert(result.into(), x.digits, || {
+                        Expression::from_subfactorial(x.expression.clone())
+                    });
+                }
+            }
+        }
+        false
+    }
+
+    fn double_factorial(&mut self, x: &State<T>) -> bool {
+        if let Some(n) = x.number.to_int() {
+            if n > 2
+                && n < self.limits.max_double_factorial
+                && double_factorial_digits(n) <= self.limits.max_digits as f64
+            {
+                if let Some(result) = double_factorial(n) {
+                    return self.try_insert(result.into(), x.digits, || {
+                        Expression::from_double_factorial(x.expression.clone())
+                    });
+                }
+            }
+        }
+        false
+    }
+
+    fn nth_prime(&mut self, x: &State<T>) -> bool {
+        if let Some(k) = x.number.to_int() {
+            if k > 0 && k as usize <= self.primes.len() {
+                let p = self.primes[k as usize - 1];
+                self.try_insert(p.into(), x.digits, || {
+                    Expression::from_nth_prime(x.expression.clone())
+                })
+            } else {
+                false
+            }
+        } else {
+            false
+        }
+    }
+
+    fn prime_pi(&mut self, x: &State<T>) -> bool {
+        if let Some(k) = x.number.to_int() {
+            if k > 2 && k <= self.limits.max_prime {
+                let pi = self.primes.partition_point(|p| *p <= k) as i64;
+                self.try_insert(pi.into(), x.digits, || {
+                    Expression::from_prime_pi(x.expression.clone())
                 })
             } else {
                 false
@@ -98,6 +259,18 @@ impl<T: Number> UnaryOperation<T> for Solver<T> {
         }
     }
 
+    default fn round(&mut self, _x: &State<T>) -> bool {
+        false
+    }
+
+    default fn percent(&mut self, _x: &State<T>) -> bool {
+        false
+    }
+
+    default fn log(&mut self, _x: &State<T>) -> bool {
+        false
+    }
+
     default fn division_diff_one(
         &mut self,
         _x: T,
@@ -109,20 +282,24 @@ impl<T: Number> UnaryOperation<T> for Solver<T> {
     }
 }
 
-impl UnaryOperation<i64> for Solver<i64> {
-    fn sqrt(&mut self, x: &State<i64>) -> bool {
-        if let Some(y) = try_sqrt(x.number) {
-            self.try_insert(y, x.digits, || {
-                Expression::from_sqrt(x.expression.clone(), 1)
-            })
-        } else {
-            false
+impl UnaryOperation<i128> for Solver<i128> {
+    fn log(&mut self, x: &State<i128>) -> bool {
+        let n = self.n;
+        let y = match i64::try_from(x.number) {
+            Ok(x) => exact_log(n, x.into()),
+            Err(_) => None,
+        };
+        match y.filter(|y| y.is_integer() && y.is_positive()) {
+            Some(y) => self.try_insert(y.to_integer().into(), x.digits, || {
+                Expression::from_log(x.expression.clone(), n)
+            }),
+            None => false,
         }
     }
 
     fn division_diff_one(
         &mut self,
-        x: i64,
+        x: i128,
         digits: usize,
         numerator: Rc<Expression>,
         denominator: Rc<Expression>,
@@ -151,15 +328,29 @@ impl UnaryOperation<i64> for Solver<i64> {
 }
 
 impl UnaryOperation<Rational64> for Solver<Rational64> {
-    fn sqrt(&mut self, x: &State<Rational64>) -> bool {
-        if let Some(p) = try_sqrt(*x.number.numer()) {
-            if let Some(q) = try_sqrt(*x.number.denom()) {
-                self.try_insert(Rational64::new_raw(p, q), x.digits, || {
-                    Expression::from_sqrt(x.expression.clone(), 1)
-                })
-            } else {
-                false
-            }
+    fn decimal(&mut self, digits: usize) -> bool {
+        self.insert_decimals(digits)
+    }
+
+    fn percent(&mut self, x: &State<Rational64>) -> bool {
+        self.insert_percent(x)
+    }
+
+    fn log(&mut self, x: &State<Rational64>) -> bool {
+        let n = self.n;
+        match exact_log(n, x.number).filter(|y| y.is_positive()) {
+            Some(y) => self.try_insert(y, x.digits, || {
+                Expression::from_log(x.expression.clone(), n)
+            }),
+            None => false,
+        }
+    }
+
+    fn round(&mut self, x: &State<Rational64>) -> bool {
+        if let Some(y) = round_rational(x.number).filter(|y| *y > 0) {
+            self.try_insert(y.into(), x.digits, || {
+                Expression::from_round(x.expression.clone())
+            })
         } else {
             false
         }
@@ -232,15 +423,11 @@ impl UnaryOperation<Rational64> for Solver<Rational64> {
 }
 
 impl UnaryOperation<IntegralQuadratic> for Solver<IntegralQuadratic> {
-    fn sqrt(&mut self, x: &State<IntegralQuadratic>) -> bool {
-        if x.number.quadratic_power() < self.limits.max_quadratic_power {
-            if let Some(result) = x.number.try_sqrt() {
-                self.try_insert(result, x.digits, || {
-                    Expression::from_sqrt(x.expression.clone(), 1)
-                })
-            } else {
-                false
-            }
+    fn round(&mut self, x: &State<IntegralQuadratic>) -> bool {
+        if let Some(y) = x.number.round().filter(|y| *y > 0) {
+            self.try_insert(y.into(), x.digits, || {
+                Expression::from_round(x.expression.clone())
+            })
         } else {
             false
         }
@@ -277,15 +464,19 @@ impl UnaryOperation<IntegralQuadratic> for Solver<IntegralQuadratic> {
 }
 
 impl UnaryOperation<RationalQuadratic> for Solver<RationalQuadratic> {
-    fn sqrt(&mut self, x: &State<RationalQuadratic>) -> bool {
-        if x.number.quadratic_power() < self.limits.max_quadratic_power {
-            if let Some(result) = x.number.try_sqrt() {
-                self.try_insert(result, x.digits, || {
-                    Expression::from_sqrt(x.expression.clone(), 1)
-                })
-            } else {
-                false
-            }
+    fn decimal(&mut self, digits: usize) -> bool {
+        self.insert_decimals(digits)
+    }
+
+    fn percent(&mut self, x: &State<RationalQuadratic>) -> bool {
+        self.insert_percent(x)
+    }
+
+    fn round(&mut self, x: &State<RationalQuadratic>) -> bool {
+        if let Some(y) = x.number.round().filter(|y| *y > 0) {
+            self.try_insert(y.into(), x.digits, || {
+                Expression::from_round(x.expression.clone())
+            })
         } else {
             false
         }
@@ -298,9 +489,14 @@ impl UnaryOperation<RationalQuadratic> for Solver<RationalQuadratic> {
         numerator: Rc<Expression>,
         denominator: Rc<Expression>,
     ) -> bool {
+        let one = RationalQuadratic::from(1);
+        let (x_minus_one, x_plus_one) = match (x.checked_sub(&one), x.checked_add(&one)) {
+            (Some(x_minus_one), Some(x_plus_one)) => (x_minus_one, x_plus_one),
+            _ => return false,
+        };
         let mut found = false;
         if x.rational_part().numer() < x.rational_part().denom() {
-            let result = -(x - 1);
+            let result = -x_minus_one;
             if self.try_insert(result, digits, || {
                 Expression::from_divide(
                     Expression::from_subtract(denominator.clone(), numerator.clone()),
@@ -318,7 +514,7 @@ impl UnaryOperation<RationalQuadratic> for Solver<RationalQuadratic> {
                 found = true;
             }
         } else if x.rational_part().numer() > x.rational_part().denom() {
-            let result = x - 1;
+            let result = x_minus_one;
             if self.try_insert(result, digits, || {
                 Expression::from_divide(
                     Expression::from_subtract(numerator.clone(), denominator.clone()),
@@ -336,7 +532,7 @@ impl UnaryOperation<RationalQuadratic> for Solver<RationalQuadratic> {
                 found = true;
             }
         }
-        let result = x + 1;
+        let result = x_plus_one;
         if self.try_insert(result, digits, || {
             Expression::from_divide(
                 Expression::from_add(numerator.clone(), denominator.clone()),
@@ -356,3 +552,129 @@ impl UnaryOperation<RationalQuadratic> for Solver<RationalQuadratic> {
         found
     }
 }
+
+impl UnaryOperation<GaussianRational> for Solver<GaussianRational> {
+    fn decimal(&mut self, digits: usize) -> bool {
+        self.insert_decimals(digits)
+    }
+
+    fn percent(&mut self, x: &State<GaussianRational>) -> bool {
+        self.insert_percent(x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::number_theory;
+    use crate::solver::Limits;
+
+    fn limits(allowed_ops: AllowedOps) -> Limits {
+        Limits {
+            allowed_ops,
+            max_digits: 32,
+            max_subfactorial: 10,
+            max_double_factorial: 12,
+            max_prime: 100,
+            max_root_degree: 6,
+            ..Limits::default()
+        }
+    }
+
+    fn apply<T: Number>(
+        n: i64,
+        limits: Limits,
+        x: i64,
+        op: impl FnOnce(&mut Solver<T>, &State<T>) -> bool,
+    ) -> Vec<String> {
+        let mut solver = Solver::new(n, limits);
+        let state = State {
+            number: x.into(),
+            digits: 1,
+            expression: Expression::from_number(x),
+        };
+        op(&mut solver, &state);
+        let mut results: Vec<_> = solver
+            .states
+            .iter()
+            .map(|(number, (expression, _))| format!("{}={}", expression, number))
+            .collect();
+        results.sort();
+        results
+    }
+
+    #[test]
+    fn roots_of_every_degree() {
+        let roots = |x| apply::<i128>(1, limits(AllowedOps::ROOT), x, |solver, x| solver.root(x));
+        assert_eq!(roots(8), ["cuberoot(8)=2"]);
+        assert_eq!(roots(16), ["root(4,16)=2"]);
+        assert_eq!(roots(-8), ["cuberoot(-8)=-2"]);
+        assert!(roots(-16).is_empty());
+        assert_eq!(roots(64), ["cuberoot(64)=4", "root(6,64)=2"]);
+        let rational_roots =
+            |x| apply::<Rational64>(1, limits(AllowedOps::ROOT), x, |solver, x| solver.root(x));
+        assert_eq!(rational_roots(81), ["root(4,81)=3"]);
+    }
+    #[test]
+    fn primes_and_prime_counts() {
+        let nth_primes = |x| {
+            apply::<i128>(1, limits(AllowedOps::NTH_PRIME), x, |solver, x| {
+                solver.nth_prime(x)
+            })
+        };
+        assert_eq!(
+            nth_primes(4),
+            [
+                "prime(4)=7",
+                "prime(prime(4))=17",
+                "prime(prime(prime(4)))=59"
+            ]
+        );
+        assert!(nth_primes(26).is_empty());
+        let prime_pis = |x| {
+            apply::<i128>(1, limits(AllowedOps::PRIME_PI), x, |solver, x| {
+                solver.prime_pi(x)
+            })
+        };
+        assert_eq!(prime_pis(10), ["pi(10)=4", "pi(pi(10))=2"]);
+        assert!(prime_pis(101).is_empty());
+        for k in 3..=100 {
+            let expected = format!("pi({})={}", k, number_theory::prime_pi(k));
+            assert!(prime_pis(k).contains(&expected));
+        }
+    }
+    #[test]
+    fn double_factorials_below_the_limit() {
+        let double_factorials =
+            |limits, x| apply::<i128>(1, limits, x, |solver, x| solver.double_factorial(x));
+        let all = limits(AllowedOps::DOUBLE_FACTORIAL);
+        assert_eq!(double_factorials(all.clone(), 7), ["7!!=105"]);
+        assert_eq!(double_factorials(all.clone(), 11), ["11!!=10395"]);
+        assert!(double_factorials(all.clone(), 12).is_empty());
+        let small = Limits {
+            max_digits: 8,
+            ..all
+        };
+        assert!(double_factorials(small, 9).is_empty());
+    }
+    #[test]
+    fn subfactorials() {
+        let subfactorials = |x| {
+            apply::<i128>(1, limits(AllowedOps::SUBFACTORIAL), x, |solver, x| {
+                solver.subfactorial(x)
+            })
+        };
+        assert_eq!(subfactorials(4), ["!(!4)=133496", "!4=9"]);
+        assert_eq!(subfactorials(5), ["!5=44"]);
+        assert!(subfactorials(10).is_empty());
+        assert!(subfactorials(1).is_empty());
+    }
+    #[test]
+    fn exact_logarithms() {
+        let logs = |n, x| apply::<i128>(n, limits(AllowedOps::LOG), x, |solver, x| solver.log(x));
+        assert_eq!(logs(2, 8), ["log(8)=3"]);
+        assert_eq!(logs(3, 81), ["log(81)=4"]);
+        assert!(logs(4, 8).is_empty());
+        assert!(logs(2, 6).is_empty());
+    }
+}
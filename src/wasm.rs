@@ -1,7 +1,6 @@
 use crate::*;
 use num::rational::Rational64;
 use serde::{Deserialize, Serialize};
-use std::rc::Rc;
 use wasm_bindgen::prelude::*;
 
 #[derive(Deserialize, Serialize)]
@@ -29,6 +28,7 @@ struct ProgressiveConfig {
     quadratic_max_digits: usize,
     quadratic_max_factorial: u32,
     quadratic_max_quadratic_power: u8,
+    max_percent_depth: Option<usize>,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -37,13 +37,22 @@ struct Solution {
     expression: String,
 }
 
+#[derive(Deserialize, Serialize)]
+struct Step {
+    status: String,
+    solution: Option<Solution>,
+}
+
+fn _to_js_value<T: Serialize>(value: &T) -> JsValue {
+    JsValue::from_serde(value).unwrap()
+}
+
 fn _serialize_output(solution: Option<(Rc<Expression>, usize)>) -> JsValue {
     if let Some((expression, digits)) = solution {
-        JsValue::from_serde(&Solution {
+        _to_js_value(&Solution {
             digits,
             expression: expression.to_latex_string(),
         })
-        .unwrap()
     } else {
         JsValue::NULL
     }
@@ -52,16 +61,17 @@ fn _serialize_output(solution: Option<(Rc<Expression>, usize)>) -> JsValue {
 #[wasm_bindgen(js_name = solveIntegral)]
 pub fn _solve_integral(n: i32, target: i32, config: &JsValue) -> JsValue {
     let config: Config = config.into_serde().unwrap();
-    let mut solver = Solver::<i64>::new(
+    let mut solver = Solver::<i128>::new(
         n as i64,
         Limits {
             max_digits: config.max_digits,
             max_factorial: config.max_factorial as i64,
             max_quadratic_power: 0,
+            ..Limits::default()
         },
     );
     _serialize_output(solver.solve(
-        target as i64,
+        target as i128,
         if config.max_depth == 0 {
             None
         } else {
@@ -79,6 +89,7 @@ pub fn _solve_rational(n: i32, target: i32, config: &JsValue) -> JsValue {
             max_digits: config.max_digits,
             max_factorial: config.max_factorial as i64,
             max_quadratic_power: 0,
+            ..Limits::default()
         },
     );
     _serialize_output(solver.solve(
@@ -100,6 +111,7 @@ pub fn _solve_integral_quadratic(n: i32, target: i32, config: &JsValue) -> JsVal
             max_digits: config.max_digits,
             max_factorial: config.max_factorial as i64,
             max_quadratic_power: config.max_quadratic_power,
+            ..Limits::default()
         },
     );
     _serialize_output(solver.solve(
@@ -121,6 +133,7 @@ pub fn _solve_ratinoal_quadratic(n: i32, target: i32, config: &JsValue) -> JsVal
             max_digits: config.max_digits,
             max_factorial: config.max_factorial as i64,
             max_quadratic_power: config.max_quadratic_power,
+            ..Limits::default()
         },
     );
     _serialize_output(solver.solve(
@@ -143,10 +156,18 @@ impl ProgressiveSolver {
     #[wasm_bindgen(constructor)]
     pub fn new(n: i32, target: i32, config: &JsValue) -> Self {
         let config: ProgressiveConfig = config.into_serde().unwrap();
+        let percent_limits = match config.max_percent_depth {
+            Some(max_percent_depth) => Limits {
+                allowed_ops: AllowedOps::STANDARD | AllowedOps::PERCENT,
+                max_percent_depth,
+                ..Limits::default()
+            },
+            None => Limits::default(),
+        };
         Self {
             solver: crate::ProgressiveSolver::new(
                 n as i64,
-                target as i64,
+                target as i128,
                 if config.max_depth == 0 {
                     None
                 } else {
@@ -156,17 +177,21 @@ impl ProgressiveSolver {
                     max_digits: config.integral_max_digits,
                     max_factorial: config.integral_max_factorial as i64,
                     max_quadratic_power: 0,
+                    ..Limits::default()
                 },
                 Limits {
                     max_digits: config.rational_max_digits,
                     max_factorial: config.rational_max_factorial as i64,
                     max_quadratic_power: 0,
+                    ..percent_limits.clone()
                 },
                 Limits {
                     max_digits: config.quadratic_max_digits,
                     max_factorial: config.quadratic_max_factorial as i64,
                     max_quadratic_power: config.quadratic_max_quadratic_power,
+                    ..percent_limits
                 },
+                None,
             ),
         }
     }
@@ -175,4 +200,34 @@ impl ProgressiveSolver {
     pub fn solve_next(&mut self) -> JsValue {
         _serialize_output(self.solver.solve_next())
     }
+
+    #[wasm_bindgen(js_name = solveStep)]
+    pub fn solve_step(&mut self) -> JsValue {
+        let step = match self.solver.solve_step() {
+            SearchOutcome::Found(expression, digits) => Step {
+                status: "found".to_string(),
+                solution: Some(Solution {
+                    digits,
+                    expression: expression.to_latex_string(),
+                }),
+            },
+            SearchOutcome::Continue => Step {
+                status: "continue".to_string(),
+                solution: None,
+            },
+            SearchOutcome::DepthLimited => Step {
+                status: "depth_limited".to_string(),
+                solution: None,
+            },
+            SearchOutcome::Exhausted => Step {
+                status: "exhausted".to_string(),
+                solution: None,
+            },
+            SearchOutcome::TimedOut => Step {
+                status: "timed_out".to_string(),
+                solution: None,
+            },
+        };
+        _to_js_value(&step)
+    }
 }
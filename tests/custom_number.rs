@@ -0,0 +1,156 @@
+use num::{Num, One, Signed, Zero};
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Rem, Sub};
+use tchisla_solver::{Limits, Number, Solver};
+
+// A minimal number type outside the crate, to check that implementing `Number` is enough to
+// drive a solver.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+struct Integer(i64);
+
+impl fmt::Display for Integer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl From<i64> for Integer {
+    fn from(x: i64) -> Self {
+        Integer(x)
+    }
+}
+
+impl Add for Integer {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Integer(self.0 + rhs.0)
+    }
+}
+
+impl Sub for Integer {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Integer(self.0 - rhs.0)
+    }
+}
+
+impl Mul for Integer {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Integer(self.0 * rhs.0)
+    }
+}
+
+impl Div for Integer {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self {
+        Integer(self.0 / rhs.0)
+    }
+}
+
+impl Rem for Integer {
+    type Output = Self;
+
+    fn rem(self, rhs: Self) -> Self {
+        Integer(self.0 % rhs.0)
+    }
+}
+
+impl Neg for Integer {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Integer(-self.0)
+    }
+}
+
+impl Zero for Integer {
+    fn zero() -> Self {
+        Integer(0)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl One for Integer {
+    fn one() -> Self {
+        Integer(1)
+    }
+}
+
+impl Num for Integer {
+    type FromStrRadixErr = <i64 as Num>::FromStrRadixErr;
+
+    fn from_str_radix(str: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+        i64::from_str_radix(str, radix).map(Integer)
+    }
+}
+
+impl Signed for Integer {
+    fn abs(&self) -> Self {
+        Integer(self.0.abs())
+    }
+
+    fn abs_sub(&self, other: &Self) -> Self {
+        Integer(self.0.abs_sub(&other.0))
+    }
+
+    fn signum(&self) -> Self {
+        Integer(self.0.signum())
+    }
+
+    fn is_positive(&self) -> bool {
+        self.0 > 0
+    }
+
+    fn is_negative(&self) -> bool {
+        self.0 < 0
+    }
+}
+
+impl Number for Integer {
+    fn to_int(self) -> Option<i64> {
+        Some(self.0)
+    }
+
+    fn is_int(self) -> bool {
+        true
+    }
+
+    fn is_rational(self) -> bool {
+        true
+    }
+
+    fn try_sqrt(self) -> Option<Self> {
+        self.0.try_sqrt().map(Integer)
+    }
+
+    fn try_nth_root(self, n: u32) -> Option<Self> {
+        self.0.try_nth_root(n).map(Integer)
+    }
+
+    fn to_f64(self) -> f64 {
+        self.0 as f64
+    }
+}
+
+#[test]
+fn solves_with_a_custom_number_type() {
+    let limits = Limits {
+        max_digits: 8,
+        max_factorial: 10,
+        ..Limits::default()
+    };
+    let (expression, digits) = Solver::<Integer>::new(4, limits.clone())
+        .solve(Integer(100), Some(6))
+        .unwrap();
+    let (_, expected_digits) = Solver::<i128>::new(4, limits).solve(100, Some(6)).unwrap();
+    assert_eq!(digits, expected_digits);
+    assert!(expression.verify(4, 100i64));
+}
@@ -0,0 +1,24 @@
+use tchisla_solver::{Limits, Solver};
+
+fn solve(n: i64, target: i128, meet_in_the_middle: bool) -> (usize, bool) {
+    let mut solver = Solver::<i128>::new(
+        n,
+        Limits {
+            max_digits: 48,
+            max_factorial: 20,
+            meet_in_the_middle,
+            ..Limits::default()
+        },
+    );
+    let (expression, digits) = solver.solve(target, Some(8)).unwrap();
+    (digits, expression.verify(n, target))
+}
+
+#[test]
+fn matches_plain_search() {
+    for (n, target) in [(4, 97), (5, 83), (7, 100), (8, 29), (3, 2021)] {
+        let (digits, verified) = solve(n, target, true);
+        assert!(verified, "{} # {}", target, n);
+        assert_eq!(digits, solve(n, target, false).0, "{} # {}", target, n);
+    }
+}
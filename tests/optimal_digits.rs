@@ -0,0 +1,164 @@
+use tchisla_solver::{Limits, ProgressiveSolver};
+
+// Minimal digit counts with the command-line limits. A pruning change that raises any of these,
+// or stops finding a solution, is a regression.
+const OPTIMAL_DIGITS: [(i64, [(i128, usize); 8]); 9] = [
+    (
+        1,
+        [
+            (5, 4),
+            (7, 4),
+            (10, 3),
+            (11, 2),
+            (13, 4),
+            (17, 5),
+            (23, 5),
+            (100, 5),
+        ],
+    ),
+    (
+        2,
+        [
+            (1, 2),
+            (7, 5),
+            (10, 4),
+            (11, 3),
+            (23, 4),
+            (31, 5),
+            (43, 5),
+            (100, 5),
+        ],
+    ),
+    (
+        3,
+        [
+            (1, 2),
+            (5, 3),
+            (7, 3),
+            (10, 4),
+            (29, 4),
+            (47, 5),
+            (61, 4),
+            (100, 4),
+        ],
+    ),
+    (
+        4,
+        [
+            (3, 3),
+            (5, 3),
+            (13, 3),
+            (17, 4),
+            (23, 3),
+            (53, 5),
+            (97, 4),
+            (100, 3),
+        ],
+    ),
+    (
+        5,
+        [
+            (2, 3),
+            (3, 4),
+            (10, 2),
+            (19, 3),
+            (29, 3),
+            (37, 5),
+            (83, 6),
+            (100, 4),
+        ],
+    ),
+    (
+        6,
+        [
+            (2, 3),
+            (5, 3),
+            (7, 3),
+            (23, 5),
+            (37, 4),
+            (67, 4),
+            (79, 6),
+            (100, 4),
+        ],
+    ),
+    (
+        7,
+        [
+            (2, 3),
+            (5, 4),
+            (11, 3),
+            (23, 6),
+            (41, 5),
+            (71, 3),
+            (73, 6),
+            (100, 6),
+        ],
+    ),
+    (
+        8,
+        [
+            (2, 2),
+            (5, 4),
+            (7, 3),
+            (13, 5),
+            (29, 6),
+            (71, 3),
+            (79, 4),
+            (89, 4),
+        ],
+    ),
+    (
+        9,
+        [
+            (2, 2),
+            (5, 3),
+            (10, 3),
+            (13, 4),
+            (71, 3),
+            (79, 3),
+            (89, 3),
+            (97, 4),
+        ],
+    ),
+];
+
+fn solve(n: i64, target: i128, max_depth: usize) -> Option<usize> {
+    let mut solver = ProgressiveSolver::new(
+        n,
+        target,
+        Some(max_depth),
+        Limits {
+            max_digits: 48,
+            max_factorial: 20,
+            ..Limits::default()
+        },
+        Limits {
+            max_digits: 30,
+            max_factorial: 12,
+            ..Limits::default()
+        },
+        Limits {
+            max_digits: 20,
+            max_factorial: 9,
+            max_quadratic_power: if n == 7 { 3 } else { 2 },
+            ..Limits::default()
+        },
+        None,
+    );
+    solver.solve().last().map(|(_, digits)| digits)
+}
+
+#[test]
+fn finds_optimal_digit_counts() {
+    for (n, targets) in OPTIMAL_DIGITS.iter() {
+        for (target, digits) in targets.iter() {
+            assert_eq!(
+                solve(*n, *target, *digits),
+                Some(*digits),
+                "{} # {}",
+                target,
+                n
+            );
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use tchisla_solver::{Expression, ParseExpressionError, RationalQuadratic};
+
+#[test]
+fn parses_and_evaluates() {
+    let expression = Expression::parse("4!+4/4", 4).unwrap();
+    assert_eq!(expression.to_string(), "4!+4/4");
+    assert_eq!(expression.evaluate(), Some(RationalQuadratic::from(25)));
+    assert_eq!(Expression::parse("((4))", 4).unwrap().to_string(), "4");
+}
+
+#[test]
+fn reports_error_positions() {
+    assert_eq!(
+        Expression::parse("5", 4),
+        Err(ParseExpressionError { position: 0 })
+    );
+    assert_eq!(
+        Expression::parse("4+", 4),
+        Err(ParseExpressionError { position: 2 })
+    );
+}
+
+#[test]
+fn rejects_deep_nesting() {
+    let nested = |depth| format!("{}4{}", "(".repeat(depth), ")".repeat(depth));
+    assert!(Expression::parse(&nested(100), 4).is_ok());
+    assert!(Expression::parse(&nested(100_000), 4).is_err());
+    let sqrts = format!("{}4{}", "sqrt(".repeat(100_000), ")".repeat(100_000));
+    assert!(Expression::parse(&sqrts, 4).is_err());
+}
+
+#[test]
+fn evaluates_sqrt_chains() {
+    let integral = Expression::parse("sqrt(sqrt(2*2*2*2))", 2).unwrap();
+    assert_eq!(integral.evaluate(), Some(RationalQuadratic::from(2)));
+    let surd = Expression::parse("sqrt(sqrt(sqrt(2)))", 2).unwrap();
+    assert_eq!(surd.evaluate().unwrap().to_string(), "sqrt(sqrt(sqrt(2)))");
+}
@@ -0,0 +1,186 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tchisla_solver::{AllowedOps, Limits, ProgressiveSolver, SearchOutcome};
+
+fn limits(max_digits: usize) -> Limits {
+    Limits {
+        max_digits,
+        max_factorial: 4,
+        ..Limits::default()
+    }
+}
+
+fn solver(n: i64, target: i128, max_depth: usize, max_digits: usize) -> ProgressiveSolver {
+    ProgressiveSolver::new(
+        n,
+        target,
+        Some(max_depth),
+        limits(max_digits),
+        limits(max_digits),
+        limits(max_digits),
+        None,
+    )
+}
+
+#[test]
+fn solve_step_matches_solve() {
+    let (expected, expected_digits) = solver(4, 100, 8, 10).solve().next().unwrap();
+    let mut stepped = solver(4, 100, 8, 10);
+    loop {
+        match stepped.solve_step() {
+            SearchOutcome::Found(expression, digits) => {
+                assert_eq!(digits, expected_digits);
+                assert_eq!(expression.evaluate(), expected.evaluate());
+                break;
+            }
+            SearchOutcome::Continue => {}
+            outcome => panic!("unexpected outcome {:?}", outcome),
+        }
+    }
+}
+
+#[test]
+fn unreachable_target_is_exhausted() {
+    let outcome = solver(2, 97, 64, 1).solve_outcome();
+    assert!(matches!(outcome, SearchOutcome::Exhausted), "{:?}", outcome);
+}
+
+#[test]
+fn hard_target_is_depth_limited() {
+    let outcome = solver(7, 1999, 2, 10).solve_outcome();
+    assert!(
+        matches!(outcome, SearchOutcome::DepthLimited),
+        "{:?}",
+        outcome
+    );
+}
+
+#[test]
+fn restored_snapshot_finishes_like_uninterrupted_run() {
+    let (expected, expected_digits) = solver(4, 619, 8, 10).solve().last().unwrap();
+    let mut interrupted = solver(4, 619, 8, 10);
+    for _ in 0..2 {
+        assert!(matches!(interrupted.solve_step(), SearchOutcome::Continue));
+    }
+    let mut restored =
+        ProgressiveSolver::restore(interrupted.save_state(), interrupted.config()).unwrap();
+    let (expression, digits) = restored.solve().last().unwrap();
+    assert_eq!(digits, expected_digits);
+    assert_eq!(expression.evaluate(), expected.evaluate());
+}
+
+#[test]
+fn snapshot_rejects_other_problems() {
+    let snapshot = solver(4, 100, 8, 10).save_state();
+    assert!(ProgressiveSolver::restore(snapshot.clone(), solver(4, 101, 8, 10).config()).is_none());
+    assert!(ProgressiveSolver::restore(snapshot, solver(5, 100, 8, 10).config()).is_none());
+}
+
+#[test]
+fn unbounded_search_keeps_full_integral_look_ahead() {
+    let mut unbounded =
+        ProgressiveSolver::new(4, 154, None, limits(10), limits(10), limits(10), None);
+    let digits: Vec<usize> = unbounded.solve().map(|(_, digits)| digits).collect();
+    assert_eq!(digits, vec![6, 5]);
+}
+
+#[test]
+fn cancellation_from_another_thread_stops_the_search() {
+    let limits = |max_digits, max_factorial| Limits {
+        max_digits,
+        max_factorial,
+        ..Limits::default()
+    };
+    let mut cancelled = ProgressiveSolver::new(
+        7,
+        1_000_000_000_000_000_000_000_000_057,
+        None,
+        limits(48, 20),
+        limits(30, 12),
+        limits(20, 9),
+        None,
+    );
+    let flag = Arc::new(AtomicBool::new(false));
+    cancelled.set_cancel_flag(flag.clone());
+    let canceller = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        flag.store(true, Ordering::Relaxed);
+    });
+    let start = Instant::now();
+    assert!(cancelled.solve().next().is_none());
+    assert!(start.elapsed() < Duration::from_secs(5));
+    canceller.join().unwrap();
+}
+
+#[test]
+fn solve_iter_streams_improving_solutions() {
+    let mut streamed = solver(4, 154, 10, 10);
+    let solutions: Vec<_> = streamed.solve_iter().collect();
+    let digits: Vec<usize> = solutions.iter().map(|(_, digits)| *digits).collect();
+    assert_eq!(digits, vec![6, 5]);
+    for (expression, _) in &solutions {
+        assert!(expression.verify(4, 154i64));
+    }
+}
+
+#[test]
+fn solve_iter_resumes_after_an_early_drop() {
+    let (_, expected_digits) = solver(4, 154, 10, 10).solve().last().unwrap();
+    let mut interrupted = solver(4, 154, 10, 10);
+    let (_, first_digits) = interrupted.solve_iter().next().unwrap();
+    assert!(first_digits > expected_digits);
+    let (expression, digits) = interrupted.solve_iter().last().unwrap();
+    assert_eq!(digits, expected_digits);
+    assert!(expression.verify(4, 154i64));
+}
+
+#[test]
+fn disabling_concat_leaves_only_single_digits() {
+    let mut allowed_ops = AllowedOps::STANDARD;
+    allowed_ops.remove(AllowedOps::CONCAT);
+    let no_concat = Limits {
+        allowed_ops,
+        ..limits(16)
+    };
+    for target in [1, 2, 10, 11, 20, 77, 100].iter() {
+        let (expression, digits) = ProgressiveSolver::new(
+            7,
+            *target,
+            Some(7),
+            no_concat.clone(),
+            no_concat.clone(),
+            no_concat.clone(),
+            None,
+        )
+        .solve()
+        .last()
+        .unwrap();
+        let display = expression.to_string();
+        let leaves: Vec<_> = display
+            .split(|c: char| !c.is_ascii_digit())
+            .filter(|leaf| !leaf.is_empty())
+            .collect();
+        assert!(leaves.iter().all(|leaf| *leaf == "7"), "{}", display);
+        assert_eq!(leaves.len(), digits);
+        let (_, concat_digits) = solver(7, *target, 7, 16).solve().last().unwrap();
+        assert!(digits >= concat_digits);
+    }
+}
+
+#[test]
+fn solves_targets_above_i64_max() {
+    let target = 1i128 << 64;
+    assert!(target > i64::MAX as i128);
+    let (expression, digits) =
+        ProgressiveSolver::new(2, target, Some(6), limits(70), limits(10), limits(10), None)
+            .solve()
+            .next()
+            .unwrap();
+    assert_eq!(digits, 4);
+    assert_eq!(
+        expression.to_string(),
+        "sqrt(sqrt(sqrt(sqrt(sqrt(2^sqrt(2^22))))))"
+    );
+}
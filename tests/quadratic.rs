@@ -0,0 +1,109 @@
+use num::rational::Rational64;
+use num::traits::Pow;
+use num::Num;
+use tchisla_solver::{DisplayStyle, Expression, RationalQuadratic, MAX_QUADRATIC_POWER};
+
+#[test]
+fn displays_surds_whose_flat_radicand_overflows_i64() {
+    let mut x = RationalQuadratic::from(7);
+    for _ in 0..7 {
+        x = x.try_sqrt().unwrap();
+    }
+    let y = x.pow(127);
+    let expected = format!("{}sqrt(7){}", "sqrt(7*".repeat(6), ")".repeat(6));
+    assert_eq!(y.to_string(), expected);
+    assert_eq!(RationalQuadratic::from_str_radix(&expected, 10), Ok(y));
+}
+
+fn nested_sqrt(x: i64, power: usize) -> RationalQuadratic {
+    (0..power).fold(RationalQuadratic::from(x), |x, _| x.try_sqrt().unwrap())
+}
+
+#[test]
+fn displays_mixed_level_surds_nested() {
+    let cases = [
+        (2, nested_sqrt(2, 1) * nested_sqrt(2, 2), "sqrt(2*sqrt(2))"),
+        (
+            2,
+            nested_sqrt(2, 1) * nested_sqrt(2, 3),
+            "sqrt(2*sqrt(sqrt(2)))",
+        ),
+        (6, nested_sqrt(6, 1) * nested_sqrt(6, 2), "sqrt(6*sqrt(6))"),
+        (
+            6,
+            nested_sqrt(216, 2) * RationalQuadratic::from(6),
+            "6*sqrt(6*sqrt(6))",
+        ),
+    ];
+    for (n, x, expected) in cases.iter() {
+        assert_eq!(x.to_string(), *expected);
+        assert_eq!(RationalQuadratic::from_str_radix(expected, 10), Ok(*x));
+        let value = Expression::parse(expected, *n).unwrap().eval_f64();
+        assert!((x.to_f64() - value).abs() < 1e-12 * value);
+    }
+}
+
+#[test]
+fn displays_surds_across_primes_by_level() {
+    let x = nested_sqrt(2, 1) * nested_sqrt(3, 2) * nested_sqrt(5, 3);
+    assert_eq!(x.to_string(), "sqrt(2*sqrt(3*sqrt(5)))");
+    assert_eq!(x.to_latex_string(), "\\sqrt{2\\sqrt{3\\sqrt{5}}}");
+    assert_eq!(x.format_with(DisplayStyle::Unicode), "√(2×√(3×√5))");
+    assert_eq!(
+        RationalQuadratic::from_str_radix("sqrt(2*sqrt(3*sqrt(5)))", 10),
+        Ok(x)
+    );
+    let expected = 2f64.sqrt() * 3f64.powf(0.25) * 5f64.powf(0.125);
+    assert!((x.to_f64() - expected).abs() < 1e-12);
+    let y = nested_sqrt(2, 1) * nested_sqrt(5, 3);
+    assert_eq!(y.to_string(), "sqrt(2*sqrt(sqrt(5)))");
+    assert_eq!(y.format_with(DisplayStyle::Unicode), "√(2×√√5)");
+}
+
+#[cfg(feature = "primes-8")]
+#[test]
+fn sqrt_11_with_eight_primes() {
+    let x = RationalQuadratic::from(11).try_sqrt().unwrap();
+    assert_eq!(x.to_string(), "sqrt(11)");
+    assert_eq!(x * x, RationalQuadratic::from(11));
+}
+
+#[cfg(not(feature = "primes-8"))]
+#[test]
+fn sqrt_11_needs_a_larger_prime_table() {
+    assert_eq!(RationalQuadratic::from(11).try_sqrt(), None);
+}
+
+#[test]
+fn rounds_surds_exactly() {
+    let sqrt_2 = RationalQuadratic::from(2).try_sqrt().unwrap();
+    assert_eq!(sqrt_2.round(), Some(1));
+    assert_eq!((-sqrt_2).round(), Some(-1));
+    assert_eq!((sqrt_2 * Rational64::new(1, 2)).round(), Some(1));
+    assert_eq!(
+        (sqrt_2 * RationalQuadratic::from(5_000_000_000_000_000_000)).round(),
+        Some(7_071_067_811_865_475_244)
+    );
+    assert_eq!((sqrt_2 * RationalQuadratic::from(i64::MAX)).round(), None);
+}
+
+#[test]
+fn nests_sqrt_chains() {
+    let four = RationalQuadratic::from(16).try_sqrt().unwrap();
+    assert_eq!(four.try_sqrt(), Some(RationalQuadratic::from(2)));
+    let mut x = RationalQuadratic::from(2);
+    for power in 1..=MAX_QUADRATIC_POWER {
+        x = x.try_sqrt().unwrap();
+        assert_eq!(x.quadratic_power(), power);
+    }
+    assert_eq!(
+        RationalQuadratic::from(2)
+            .try_sqrt()
+            .and_then(|x| x.try_sqrt())
+            .and_then(|x| x.try_sqrt())
+            .unwrap()
+            .to_string(),
+        "sqrt(sqrt(sqrt(2)))"
+    );
+    assert_eq!(x.try_sqrt(), None);
+}
@@ -0,0 +1,57 @@
+#![cfg(feature = "serde")]
+
+use tchisla_solver::{Expression, RationalQuadratic, Rc};
+
+#[test]
+fn expression_round_trip() {
+    for source in ["sqrt(4!)+4/4", "((4+4)*4)^sqrt(4)", "-4!!-.4"] {
+        let expression = Expression::parse(source, 4).unwrap();
+        let json = serde_json::to_string(&expression).unwrap();
+        let restored: Rc<Expression> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, expression);
+        assert_eq!(restored.to_string(), expression.to_string());
+    }
+}
+
+#[test]
+fn rejects_zero_sqrt_order() {
+    let json = r#"[{"op_tag":7,"value":0,"left":1,"right":-1},{"op_tag":0,"value":4,"left":-1,"right":-1}]"#;
+    assert!(serde_json::from_str::<Expression>(json).is_err());
+}
+
+#[test]
+fn quadratic_round_trip() {
+    let x = RationalQuadratic::from(18).try_sqrt().unwrap();
+    let json = serde_json::to_string(&x).unwrap();
+    assert_eq!(serde_json::from_str::<RationalQuadratic>(&json).unwrap(), x);
+}
+
+#[test]
+fn rejects_unreduced_quadratic() {
+    let padding = ",0".repeat(tchisla_solver::PRIMES.len() - 1);
+    let invalid = [
+        format!(
+            r#"{{"rational_part":[6,2],"quadratic_part":[0{}],"quadratic_power":0}}"#,
+            padding
+        ),
+        format!(
+            r#"{{"rational_part":[3,1],"quadratic_part":[2{}],"quadratic_power":1}}"#,
+            padding
+        ),
+        format!(
+            r#"{{"rational_part":[3,1],"quadratic_part":[2{}],"quadratic_power":2}}"#,
+            padding
+        ),
+        format!(
+            r#"{{"rational_part":[0,1],"quadratic_part":[1{}],"quadratic_power":1}}"#,
+            padding
+        ),
+    ];
+    for json in invalid {
+        assert!(
+            serde_json::from_str::<RationalQuadratic>(&json).is_err(),
+            "{}",
+            json
+        );
+    }
+}